@@ -1,20 +1,438 @@
+use std::collections::HashMap;
 use serde::Deserialize;
+use crate::utils::find_in_path;
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Deserialize, Default)]
 pub struct Target {
     pub name: String,
     pub address: String,
+    #[serde(default)]
+    pub group: Option<String>, // Для запуску на всіх серверах групи
+    #[serde(default)]
+    pub connect: Option<ConnectKind>, // Без нього — за портом (3389 RDP, 22 SSH, 80/443 web)
+    #[serde(default)]
+    pub user: Option<String>, // Логін для RDP/SSH-сесії
+    #[serde(default)]
+    pub url: Option<String>, // Адреса для браузера, якщо не http(s)://host:port
+    #[serde(default)]
+    pub mac: Option<String>, // Для Wake-on-LAN
+    #[serde(default)]
+    pub broadcast: Option<String>, // Широкомовна адреса підмережі цілі замість `wol.broadcast`
+    #[serde(default)]
+    pub on_down: Option<TargetHook>, // Автоматичне виправлення, коли сервер впав
+    #[serde(default)]
+    pub on_up: Option<TargetHook>,
+}
+
+// Хук монітора: команда з `commands`, запускається з `{target.*}` цього сервера
+#[derive(Clone, Deserialize)]
+pub struct TargetHook {
+    pub command: String,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    #[serde(default = "default_cooldown")]
+    pub cooldown_secs: u64, // Мінімальна пауза між запусками
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32, // Скільки разів пробуємо, поки сервер не повернеться (on_down)
+}
+
+fn default_cooldown() -> u64 {
+    300
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+// Тип інтерактивної сесії до сервера
+#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectKind {
+    Rdp,
+    Ssh,
+    Web,
+}
+
+// Клієнти для сесій по ОС; чого немає — береться вбудований варіант (mstsc, xdg-open, open ...)
+#[derive(Clone, Deserialize, Default)]
+pub struct ConnectConfig {
+    #[serde(default)]
+    pub rdp: HashMap<String, PlatformVariant>,
+    #[serde(default)]
+    pub ssh: HashMap<String, PlatformVariant>,
+    #[serde(default)]
+    pub web: HashMap<String, PlatformVariant>,
+}
+
+// Wake-on-LAN: куди слати magic packet і скільки чекати, поки сервер підніметься
+#[derive(Clone, Deserialize)]
+pub struct WolConfig {
+    #[serde(default = "default_broadcast")]
+    pub broadcast: String,
+    #[serde(default = "default_wol_port")]
+    pub port: u16,
+    #[serde(default = "default_wake_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for WolConfig {
+    fn default() -> Self {
+        WolConfig { broadcast: default_broadcast(), port: default_wol_port(), timeout_secs: default_wake_timeout() }
+    }
+}
+
+fn default_broadcast() -> String {
+    "255.255.255.255".to_string()
+}
+
+fn default_wol_port() -> u16 {
+    9
+}
+
+fn default_wake_timeout() -> u64 {
+    300
+}
+
+// Вбудована діагностика (сканер портів, DNS, traceroute)
+#[derive(Clone, Deserialize)]
+pub struct DiagConfig {
+    #[serde(default)]
+    pub dns_server: Option<String>, // Без нього — з /etc/resolv.conf або 8.8.8.8
+    #[serde(default = "default_scan_concurrency")]
+    pub scan_concurrency: usize,
+    #[serde(default = "default_probe_timeout")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u32,
+}
+
+impl Default for DiagConfig {
+    fn default() -> Self {
+        DiagConfig { dns_server: None, scan_concurrency: default_scan_concurrency(), timeout_ms: default_probe_timeout(), max_hops: default_max_hops() }
+    }
+}
+
+fn default_scan_concurrency() -> usize {
+    100
+}
+
+fn default_probe_timeout() -> u64 {
+    800
+}
+
+fn default_max_hops() -> u32 {
+    30
+}
+
+// Періодичне сканування мереж: знімок у inventory.json, зміни — у Logs і сповіщення
+#[derive(Clone, Deserialize)]
+pub struct DiscoveryConfig {
+    #[serde(default)]
+    pub networks: Vec<String>, // "192.168.244.0/24"; порожньо — сканування вимкнено
+    #[serde(default = "default_discovery_ports")]
+    pub ports: Vec<u16>,
+    #[serde(default = "default_discovery_interval")]
+    pub interval_mins: u64,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        DiscoveryConfig { networks: Vec::new(), ports: default_discovery_ports(), interval_mins: default_discovery_interval() }
+    }
+}
+
+fn default_discovery_ports() -> Vec<u16> {
+    crate::diag::DISCOVERY_PORTS.to_vec()
+}
+
+fn default_discovery_interval() -> u64 {
+    60
+}
+
+// Тип параметра визначає валідацію та спосіб введення у формі
+#[derive(Clone, Deserialize, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamKind {
+    #[default]
+    String,
+    Int,
+    Ip,
+    Hostname,
+    Choice, // Вибір зі списку `choices`
+    Target, // Вибір з `targets` у конфігу
+}
+
+// Іменований плейсхолдер `{name}` в аргументах команди
+#[derive(Clone, Deserialize, PartialEq, Debug)]
+pub struct CommandParam {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: ParamKind,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>, // Regex, якому має відповідати все значення
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+// Рівень небезпеки: від нього залежить, як саме треба підтвердити запуск
+#[derive(Clone, Copy, Deserialize, PartialEq, PartialOrd, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DangerLevel {
+    #[default]
+    Safe,
+    Caution,  // Enter у вікні підтвердження
+    Critical, // Треба ввести назву команди
+}
+
+// Варіант команди для конкретної ОС ("windows", "linux", "macos")
+#[derive(Clone, Deserialize)]
+pub struct PlatformVariant {
+    pub cmd: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+// Чи можна запустити команду на цій машині (визначається при завантаженні конфігу)
+#[derive(Clone, PartialEq, Default, Debug)]
+pub enum Availability {
+    #[default]
+    Available,
+    Unsupported, // Не для цієї ОС
+    NotFound,    // Програми немає в PATH
+}
+
+// Перевірка ключа хоста: strict — лише відомі, accept-new — запам'ятати новий, off — не перевіряти
+#[derive(Clone, Copy, Deserialize, PartialEq, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum KnownHosts {
+    #[default]
+    Strict,
+    AcceptNew,
+    Off,
+}
+
+// Виконання через системний `ssh` (ключ або агент; пароль не питаємо — BatchMode)
+#[derive(Clone, Deserialize)]
+pub struct SshSettings {
+    #[serde(default = "default_ssh_host")]
+    pub host: String, // Шаблон, за замовчуванням сервер з таблиці
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub key: Option<String>, // Файл ключа; без нього — ssh-agent / ~/.ssh
+    #[serde(default)]
+    pub known_hosts: KnownHosts,
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u32,
+}
+
+fn default_ssh_host() -> String {
+    "{target.host}".to_string()
+}
+
+fn default_connect_timeout() -> u32 {
+    10
 }
 
 #[derive(Clone, Deserialize)]
 pub struct AdminCommand {
     pub name: String,
+    #[serde(default)]
     pub cmd: String,
+    #[serde(default)]
     pub args: Vec<String>,
+    // Кодування виводу: "utf-8", "ibm866", "windows-1251", ... або "auto" (за замовчуванням)
+    #[serde(default = "default_encoding")]
+    pub encoding: String,
+    #[serde(default)]
+    pub params: Vec<CommandParam>,
+    #[serde(default)]
+    pub confirm: bool,
+    #[serde(default)]
+    pub danger_level: DangerLevel,
+    #[serde(default)]
+    pub dry_run: bool, // Показати розгорнутий рядок запуску перед виконанням
+    #[serde(default)]
+    pub only_on: Vec<String>,
+    #[serde(default)]
+    pub platforms: HashMap<String, PlatformVariant>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>, // Значення можуть містити ${VAR} з батьківського оточення
+    #[serde(default)]
+    pub clear_env: bool,
+    #[serde(default)]
+    pub stdin: Option<String>, // Шаблон для stdin (`{selection}` — виділення в редакторі)
+    #[serde(default)]
+    pub stdin_from_selection: bool,
+    // `cmd`/`args` виконуються на віддаленому хості; `cwd` теж віддалений, `env` і `clear_env` — через `env` на хості
+    #[serde(default)]
+    pub ssh: Option<SshSettings>,
+    #[serde(skip)]
+    pub availability: Availability,
 }
 
-#[derive(Deserialize)]
+impl AdminCommand {
+    pub fn needs_confirmation(&self) -> bool {
+        self.confirm || self.dry_run || self.danger_level != DangerLevel::Safe
+    }
+
+    // Команда посилається на обраний у таблиці сервер
+    pub fn uses_target(&self) -> bool {
+        let ssh_fields = self.ssh.iter().flat_map(|s| std::iter::once(&s.host).chain(s.user.iter()));
+        std::iter::once(&self.cmd).chain(self.args.iter()).chain(ssh_fields).any(|a| a.contains("{target"))
+    }
+
+    // Програма, яку запускаємо локально
+    pub fn local_program(&self) -> &str {
+        if self.ssh.is_some() { "ssh" } else { &self.cmd }
+    }
+
+    // Поля форми: оголошені params, або один рядок для старого `%INPUT%`
+    pub fn form_params(&self) -> Vec<CommandParam> {
+        if !self.params.is_empty() { return self.params.clone(); }
+        if self.args.iter().any(|a| a.contains("%INPUT%")) {
+            return vec![CommandParam { name: "INPUT".to_string(), label: Some("Аргумент (IP/Host)".to_string()), kind: ParamKind::String, default: None, pattern: None, choices: Vec::new() }];
+        }
+        Vec::new()
+    }
+}
+
+fn default_encoding() -> String {
+    "auto".to_string()
+}
+
+// Що робити, якщо крок ранбука завершився з помилкою
+#[derive(Clone, Deserialize, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    #[default]
+    Stop,
+    Continue,
+    Rollback, // Запустити `rollback` і зупинитись
+}
+
+#[derive(Clone, Deserialize)]
+pub struct RunbookStep {
+    pub command: String, // Назва команди з `commands`
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub values: HashMap<String, String>, // Значення параметрів; можна посилатись на `{змінні}` попередніх кроків
+    #[serde(default)]
+    pub target: Option<String>, // Назва сервера для `{target.*}`
+    #[serde(default)]
+    pub capture: Option<String>, // Зберегти stdout у змінну з цією назвою
+    #[serde(default)]
+    pub on_failure: OnFailure,
+    #[serde(default)]
+    pub rollback: Option<String>, // Назва команди для відкату
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Runbook {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<RunbookStep>,
+}
+
+// Команда за розкладом: cron ("*/5 * * * *") або кожні N хвилин
+#[derive(Clone, Deserialize)]
+pub struct ScheduledCommand {
+    pub command: String, // Назва команди з `commands`
+    #[serde(default)]
+    pub cron: Option<String>,
+    #[serde(default)]
+    pub every_minutes: Option<u32>,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize, Default)]
 pub struct AppConfig {
     pub targets: Vec<Target>,
     pub commands: Vec<AdminCommand>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub runbooks: Vec<Runbook>,
+    #[serde(default)]
+    pub schedules: Vec<ScheduledCommand>,
+    #[serde(default)]
+    pub connect: ConnectConfig,
+    #[serde(default)]
+    pub wol: WolConfig,
+    #[serde(default)]
+    pub diag: DiagConfig,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+}
+
+impl AppConfig {
+    // Підставляє варіанти під поточну ОС і перевіряє, що програма є в PATH
+    pub fn resolve_platform(&mut self) {
+        let os = std::env::consts::OS;
+        for cmd in &mut self.commands {
+            // Для ssh ОС віддаленого хоста невідома: варіанти й only_on не застосовуємо
+            if cmd.ssh.is_some() {
+                cmd.availability = if find_in_path("ssh").is_some() { Availability::Available } else { Availability::NotFound };
+                continue;
+            }
+            if let Some(variant) = cmd.platforms.get(os) {
+                cmd.cmd = variant.cmd.clone();
+                cmd.args = variant.args.clone();
+            }
+            let supported = cmd.only_on.is_empty() || cmd.only_on.iter().any(|o| o.eq_ignore_ascii_case(os));
+            cmd.availability = if !supported || cmd.cmd.is_empty() { Availability::Unsupported }
+                else if !cmd.cmd.contains('{') && find_in_path(&cmd.cmd).is_none() { Availability::NotFound }
+                else { Availability::Available };
+        }
+    }
+}
+
+// Дописує цілі в масив "targets" у config.json як є, рядок на ціль: решта файлу (порядок, коментарі-відступи) не чіпається
+pub fn append_targets(path: &str, targets: &[Target]) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let start = text.find("\"targets\"").ok_or_else(|| format!("{}: немає масиву \"targets\"", path))?;
+    let open = start + text[start..].find('[').ok_or_else(|| format!("{}: немає масиву \"targets\"", path))?;
+
+    // Кінець масиву: враховуємо вкладені дужки і рядки з екрануванням
+    let (mut depth, mut in_string, mut escaped, mut close) = (0, false, false, None);
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' | '{' if !in_string => depth += 1,
+            ']' | '}' if !in_string => { depth -= 1; if depth == 0 { close = Some(open + i); break; } }
+            _ => {}
+        }
+    }
+    let close = close.ok_or_else(|| format!("{}: незакритий масив \"targets\"", path))?;
+
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    let lines: Vec<String> = targets.iter().map(|t| format!("{{ \"name\": {}, \"address\": {} }}", quote(&t.name), quote(&t.address))).collect();
+    let body_end = text[..close].trim_end().len();
+    let separator = if text[..body_end].ends_with('[') { "\n" } else { ",\n" };
+    let updated = format!("{}{}{}\n{}", &text[..body_end], separator, lines.join(",\n"), &text[close..]);
+
+    // Перевіряємо, що файл лишився валідним, перш ніж перезаписати
+    serde_json::from_str::<AppConfig>(&updated).map_err(|e| format!("{}: не вдалося оновити ({})", path, e))?;
+    std::fs::write(path, updated).map_err(|e| format!("{}: {}", path, e))
+}
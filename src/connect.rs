@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fs, path::PathBuf, process::{Command, Stdio}, thread};
+use chrono::Local;
+
+use crate::config::{ConnectConfig, ConnectKind, PlatformVariant, Target};
+use crate::utils::{expand_template, split_address, target_values};
+
+pub fn kind_label(kind: ConnectKind) -> &'static str {
+    match kind {
+        ConnectKind::Rdp => "RDP",
+        ConnectKind::Ssh => "SSH",
+        ConnectKind::Web => "Web",
+    }
+}
+
+// Тип сесії за замовчуванням: явний `connect` у цілі або за портом
+pub fn default_kind(target: &Target) -> ConnectKind {
+    if let Some(kind) = target.connect { return kind; }
+    match split_address(&target.address).1.as_str() {
+        "22" => ConnectKind::Ssh,
+        "80" | "443" | "8080" | "8443" => ConnectKind::Web,
+        _ => ConnectKind::Rdp,
+    }
+}
+
+// Вбудовані клієнти; `{file}` — згенерований .rdp, `{dest}` — user@host, `{url}` — адреса для браузера
+fn builtin_launcher(kind: ConnectKind, os: &str) -> PlatformVariant {
+    let (cmd, args): (&str, &[&str]) = match (kind, os) {
+        (ConnectKind::Rdp, "windows") => ("mstsc", &["{file}"]),
+        (ConnectKind::Rdp, "macos") => ("open", &["{file}"]),
+        (ConnectKind::Rdp, _) => ("xdg-open", &["{file}"]),
+        (ConnectKind::Ssh, "windows") => ("cmd", &["/c", "start", "SSH {target.name}", "ssh", "-p", "{ssh_port}", "{dest}"]),
+        (ConnectKind::Ssh, "macos") => ("osascript", &["-e", "tell application \"Terminal\" to do script \"ssh -p {ssh_port} {dest}\""]),
+        (ConnectKind::Ssh, _) => ("x-terminal-emulator", &["-e", "ssh", "-p", "{ssh_port}", "{dest}"]),
+        (ConnectKind::Web, "windows") => ("rundll32", &["url.dll,FileProtocolHandler", "{url}"]),
+        (ConnectKind::Web, "macos") => ("open", &["{url}"]),
+        (ConnectKind::Web, _) => ("xdg-open", &["{url}"]),
+    };
+    PlatformVariant { cmd: cmd.to_string(), args: args.iter().map(|a| a.to_string()).collect() }
+}
+
+fn launcher(kind: ConnectKind, config: &ConnectConfig) -> PlatformVariant {
+    let os = std::env::consts::OS;
+    let configured = match kind {
+        ConnectKind::Rdp => &config.rdp,
+        ConnectKind::Ssh => &config.ssh,
+        ConnectKind::Web => &config.web,
+    };
+    configured.get(os).cloned().unwrap_or_else(|| builtin_launcher(kind, os))
+}
+
+// Мінімальний .rdp: адреса, логін (якщо є) і запит пароля в самому клієнті
+fn write_rdp_file(target: &Target) -> Result<PathBuf, String> {
+    let mut lines = vec![
+        format!("full address:s:{}", target.address),
+        "prompt for credentials:i:1".to_string(),
+        "screen mode id:i:2".to_string(),
+        "authentication level:i:2".to_string(),
+    ];
+    if let Some(user) = &target.user { lines.push(format!("username:s:{}", user)); }
+    let name: String = target.name.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    let path = std::env::temp_dir().join(format!("admin_console_{}.rdp", name));
+    fs::write(&path, lines.join("\r\n") + "\r\n").map_err(|e| format!("не вдалося записати {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn session_values(kind: ConnectKind, target: &Target) -> Result<HashMap<String, String>, String> {
+    let (host, port) = split_address(&target.address);
+    let mut values: HashMap<String, String> = target_values("target", target).into_iter().collect();
+    let ssh_port = if default_kind(target) == ConnectKind::Ssh && !port.is_empty() { port.clone() } else { "22".to_string() };
+    let scheme = if port == "443" || port == "8443" { "https" } else { "http" };
+    values.insert("ssh_port".to_string(), ssh_port);
+    values.insert("dest".to_string(), match &target.user { Some(user) => format!("{}@{}", user, host), None => host.clone() });
+    values.insert("user".to_string(), target.user.clone().unwrap_or_default());
+    values.insert("url".to_string(), target.url.clone().unwrap_or_else(|| format!("{}://{}/", scheme, target.address)));
+    if kind == ConnectKind::Rdp { values.insert("file".to_string(), write_rdp_file(target)?.display().to_string()); }
+    Ok(values)
+}
+
+// Відкриває сесію окремим процесом (не job: клієнт живе своїм життям). Повертає рядок для Logs
+pub fn open_session(kind: ConnectKind, target: &Target, config: &ConnectConfig) -> String {
+    let timestamp = Local::now().format("%H:%M:%S");
+    let values = match session_values(kind, target) {
+        Ok(v) => v,
+        Err(e) => return format!("[{}] ❌ {} → '{}': {}", timestamp, kind_label(kind), target.name, e),
+    };
+    let variant = launcher(kind, config);
+    let program = expand_template(&variant.cmd, &values);
+    let args: Vec<String> = variant.args.iter().map(|a| expand_template(a, &values)).collect();
+    match Command::new(&program).args(&args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || { let _ = child.wait(); }); // Прибрати процес після закриття клієнта
+            format!("[{}] 🖥 {} → '{}': {} {}", timestamp, kind_label(kind), target.name, program, args.join(" "))
+        }
+        Err(e) => format!("[{}] ❌ {} → '{}': не вдалося запустити {}: {}", timestamp, kind_label(kind), target.name, program, e),
+    }
+}
@@ -0,0 +1,496 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::config::{DiagConfig, Target};
+use crate::dns::{lookup_types, query, resolver, reverse_lookup, type_name};
+use crate::types::{ActiveView, AppEvent, DiagRun, DiagTool};
+use crate::utils::split_address;
+
+const COMMON_PORTS: [u16; 24] = [21, 22, 23, 25, 53, 80, 110, 111, 135, 139, 143, 443, 445, 993, 995, 1433, 3306, 3389, 5432, 5900, 5985, 8080, 8443, 9100];
+const TRACE_ROUNDS: usize = 3;
+pub const DISCOVERY_PORTS: [u16; 6] = [22, 80, 135, 443, 445, 3389];
+const PREFERRED_PORTS: [u16; 4] = [3389, 22, 443, 80]; // Порт для address імпортованої цілі
+pub const NEW_HOST: &str = "🆕 нова";
+
+pub fn tool_label(tool: DiagTool) -> &'static str {
+    match tool {
+        DiagTool::PortScan => "Port scan",
+        DiagTool::Dns => "DNS lookup",
+        DiagTool::Trace => "Traceroute (TCP)",
+        DiagTool::Reverse => "Reverse lookup",
+        DiagTool::Discover => "Discovery",
+    }
+}
+
+// Підказка до рядка вводу
+pub fn tool_hint(tool: DiagTool) -> &'static str {
+    match tool {
+        DiagTool::PortScan => "хост [порти: 22,80,8000-8100]",
+        DiagTool::Dns => "ім'я [@dns-сервер]",
+        DiagTool::Trace => "хост[:порт] (порт 80 за замовчуванням)",
+        DiagTool::Reverse => "IP або ім'я",
+        DiagTool::Discover => "мережа/маска [порти], напр. 192.168.1.0/24 22,3389",
+    }
+}
+
+pub fn headers(tool: DiagTool) -> &'static [&'static str] {
+    match tool {
+        DiagTool::PortScan => &["Port", "State", "Service", "Time"],
+        DiagTool::Dns => &["Name", "Type", "TTL", "Data"],
+        DiagTool::Trace => &["#", "Address", "Name", "Loss", "Last", "Avg", "Best", "Worst"],
+        DiagTool::Reverse => &["Address", "PTR"],
+        DiagTool::Discover => &["Address", "Name", "Ports", "Status"],
+    }
+}
+
+// "22,80,8000-8100" -> список портів без повторів
+pub fn parse_ports(spec: &str) -> Result<Vec<u16>, String> {
+    let mut ports = Vec::new();
+    let mut seen = HashSet::new();
+    for part in spec.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let (from, to) = match part.split_once('-') {
+            Some((a, b)) => (a.trim().parse::<u16>(), b.trim().parse::<u16>()),
+            None => (part.parse::<u16>(), part.parse::<u16>()),
+        };
+        let (Ok(from), Ok(to)) = (from, to) else { return Err(format!("некоректні порти '{}'", part)); };
+        if from == 0 || from > to { return Err(format!("некоректний діапазон '{}'", part)); }
+        for port in from..=to { if seen.insert(port) { ports.push(port); } }
+    }
+    if ports.is_empty() { return Err("не вказано портів".to_string()); }
+    Ok(ports)
+}
+
+// "192.168.244.0/24" -> адреси хостів (без адреси мережі й broadcast, якщо маска ширша за /31)
+pub fn parse_cidr(text: &str) -> Result<Vec<Ipv4Addr>, String> {
+    let (addr, prefix) = text.split_once('/').unwrap_or((text, "32"));
+    let addr: Ipv4Addr = addr.parse().map_err(|_| format!("некоректна адреса '{}'", addr))?;
+    let prefix: u32 = prefix.parse().ok().filter(|p| *p <= 32).ok_or_else(|| format!("некоректна маска '/{}'", prefix))?;
+    if prefix < 16 { return Err("завелика мережа: не ширше /16".to_string()); }
+    let network = u32::from(addr) & (u32::MAX << (32 - prefix));
+    let last = network | u32::MAX.checked_shr(prefix).unwrap_or(0); // /32: зсув на 32 біти не визначений
+    let (first, last) = if prefix >= 31 { (network, last) } else { (network + 1, last - 1) };
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+pub fn service_name(port: u16) -> &'static str {
+    match port {
+        21 => "ftp", 22 => "ssh", 23 => "telnet", 25 => "smtp", 53 => "dns", 80 => "http", 110 => "pop3", 111 => "rpcbind",
+        135 => "msrpc", 139 => "netbios", 143 => "imap", 443 => "https", 445 => "smb", 993 => "imaps", 995 => "pop3s",
+        1433 => "mssql", 3306 => "mysql", 3389 => "rdp", 5432 => "postgres", 5900 => "vnc", 5985 => "winrm",
+        8080 => "http-alt", 8443 => "https-alt", 9100 => "printer",
+        _ => "",
+    }
+}
+
+// Alt+7: повертаємось до останнього запуску (той самий інструмент і ввід)
+pub fn diag_view(last: Option<&DiagRun>) -> ActiveView {
+    ActiveView::Diagnostics { tool: last.map(|r| r.tool).unwrap_or(DiagTool::PortScan), input: last.map(|r| r.input.clone()).unwrap_or_default(), selected: 0, marked: BTreeSet::new() }
+}
+
+// Нові хости з результатів Discovery (за ключами рядків) як цілі для config.json
+pub fn discovered_targets(run: &DiagRun, keys: &[usize], existing: &[Target]) -> Vec<Target> {
+    let mut result: Vec<Target> = Vec::new();
+    for cells in keys.iter().filter_map(|k| run.rows.iter().find(|(key, _)| key == k)).map(|(_, cells)| cells) {
+        let [ip, ptr, ports, status] = cells.as_slice() else { continue };
+        if status != NEW_HOST { continue; }
+        let ports: Vec<u16> = ports.split(',').filter_map(|p| p.trim().parse().ok()).collect();
+        let port = PREFERRED_PORTS.iter().find(|p| ports.contains(p)).or(ports.first()).copied().unwrap_or(80);
+        // Коротке ім'я з PTR ("kiev-dc3.corp.local" -> "kiev-dc3"), інакше IP
+        let base = ptr.split('.').next().filter(|s| !s.is_empty()).unwrap_or(ip);
+        let taken = |name: &str| existing.iter().chain(result.iter()).any(|t| t.name.eq_ignore_ascii_case(name));
+        let name = if taken(base) { format!("{} ({})", base, ip) } else { base.to_string() };
+        result.push(Target { name, address: format!("{}:{}", ip, port), ..Default::default() });
+    }
+    result
+}
+
+// Таблиця результатів як TSV для буфера обміну
+pub fn rows_as_text(run: &DiagRun) -> String {
+    let mut lines = vec![headers(run.tool).join("\t")];
+    lines.extend(run.rows.iter().map(|(_, cells)| cells.join("\t")));
+    if let Some(summary) = &run.summary { lines.push(summary.clone()); }
+    lines.join("\n")
+}
+
+// Назва сервера з config.targets замість адреси: для traceroute — з його портом
+pub fn resolve_input(tool: DiagTool, input: &str, targets: &[Target]) -> String {
+    let mut words = input.split_whitespace();
+    let Some(first) = words.next() else { return String::new() };
+    let rest: Vec<&str> = words.collect();
+    let first = match targets.iter().find(|t| t.name.eq_ignore_ascii_case(first)) {
+        Some(t) if tool == DiagTool::Trace => t.address.clone(),
+        Some(t) => split_address(&t.address).0,
+        None => first.to_string(),
+    };
+    std::iter::once(first.as_str()).chain(rest).collect::<Vec<_>>().join(" ")
+}
+
+fn resolve_host(host: &str) -> Result<IpAddr, String> {
+    if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() { return Ok(ip); }
+    (host, 0).to_socket_addrs().map_err(|e| format!("{}: {}", host, e))?.next().map(|a| a.ip()).ok_or_else(|| format!("{}: адресу не знайдено", host))
+}
+
+// Запуск у фоні; рядки йдуть як DiagRow, підсумок — DiagDone. Прапорець зупиняє роботу
+pub fn start_diag(run: usize, tool: DiagTool, input: String, cfg: DiagConfig, targets: Vec<Target>, tx: Sender<AppEvent>) -> Arc<AtomicBool> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = cancel.clone();
+    thread::spawn(move || {
+        let started = Instant::now();
+        let row = |key: usize, cells: Vec<String>| { let _ = tx.send(AppEvent::DiagRow { run, key, cells }); };
+        let result = match tool {
+            DiagTool::PortScan => port_scan(&input, &cfg, &flag, &row),
+            DiagTool::Dns => dns_lookup(&input, &cfg, &flag, &row),
+            DiagTool::Trace => trace(&input, &cfg, &flag, &row),
+            DiagTool::Reverse => reverse(&input, &cfg, &row),
+            DiagTool::Discover => discover(&input, &cfg, &targets, &flag, &row),
+        };
+        let summary = match result {
+            Ok(text) if flag.load(Ordering::Relaxed) => format!("⏹ Зупинено: {}", text),
+            Ok(text) => format!("✅ {} ({:.1} с)", text, started.elapsed().as_secs_f32()),
+            Err(e) => format!("❌ {}", e),
+        };
+        let _ = tx.send(AppEvent::DiagDone { run, summary });
+    });
+    cancel
+}
+
+// Паралельне TCP-підключення до портів, не більше `scan_concurrency` одночасно
+fn port_scan(input: &str, cfg: &DiagConfig, cancel: &AtomicBool, row: &(dyn Fn(usize, Vec<String>) + Sync)) -> Result<String, String> {
+    let mut words = input.split_whitespace();
+    let host = words.next().ok_or("вкажіть хост")?;
+    let spec: Vec<&str> = words.collect();
+    let ports = if spec.is_empty() { COMMON_PORTS.to_vec() } else { parse_ports(&spec.join(","))? };
+    let ip = resolve_host(host)?;
+    let timeout = Duration::from_millis(cfg.timeout_ms);
+    let next = AtomicUsize::new(0);
+    let open = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..cfg.scan_concurrency.clamp(1, 1000).min(ports.len()) {
+            scope.spawn(|| {
+                while !cancel.load(Ordering::Relaxed) {
+                    let Some(&port) = ports.get(next.fetch_add(1, Ordering::Relaxed)) else { break };
+                    let start = Instant::now();
+                    if TcpStream::connect_timeout(&SocketAddr::new(ip, port), timeout).is_ok() {
+                        open.fetch_add(1, Ordering::Relaxed);
+                        row(port as usize, vec![port.to_string(), "open".to_string(), service_name(port).to_string(), format!("{} ms", start.elapsed().as_millis())]);
+                    }
+                }
+            });
+        }
+    });
+    let scanned = next.load(Ordering::Relaxed).min(ports.len());
+    Ok(format!("{}: відкрито {} з {} перевірених портів", ip, open.load(Ordering::Relaxed), scanned))
+}
+
+// Усі основні типи записів по черзі; для IP — одразу PTR
+fn dns_lookup(input: &str, cfg: &DiagConfig, cancel: &AtomicBool, row: &dyn Fn(usize, Vec<String>)) -> Result<String, String> {
+    let mut name = None;
+    let mut server_override = None;
+    for word in input.split_whitespace() {
+        match word.strip_prefix('@') { Some(s) => server_override = Some(s.to_string()), None => name = Some(word.to_string()) }
+    }
+    let name = name.ok_or("вкажіть ім'я")?;
+    let server = resolver(server_override.as_deref().or(cfg.dns_server.as_deref()));
+    let timeout = Duration::from_millis(cfg.timeout_ms.max(1500));
+    if let Ok(ip) = name.parse::<IpAddr>() {
+        let names = reverse_lookup(server, ip, timeout)?;
+        for (i, ptr) in names.iter().enumerate() { row(i, vec![ip.to_string(), "PTR".to_string(), String::new(), ptr.clone()]); }
+        return Ok(format!("{} PTR від {}", names.len(), server));
+    }
+
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+    for rtype in lookup_types(&name) {
+        if cancel.load(Ordering::Relaxed) { break; }
+        match query(server, &name, rtype, timeout) {
+            Ok(records) => {
+                // CNAME повертається у відповідь на кожен тип — показуємо один раз
+                for r in records {
+                    if seen.insert((r.name.clone(), r.rtype, r.data.clone())) { row(seen.len(), vec![r.name, type_name(r.rtype), r.ttl.to_string(), r.data]); }
+                }
+            }
+            Err(e) if e == "NXDOMAIN" => return Err(format!("{}: NXDOMAIN ({})", name, server)),
+            Err(e) => errors.push(format!("{}: {}", type_name(rtype), e)),
+        }
+    }
+    let mut summary = format!("{}: {} записів від {}", name, seen.len(), server);
+    if !errors.is_empty() { summary.push_str(&format!("; помилки — {}", errors.join(", "))); }
+    Ok(summary)
+}
+
+fn reverse(input: &str, cfg: &DiagConfig, row: &dyn Fn(usize, Vec<String>)) -> Result<String, String> {
+    let host = input.split_whitespace().next().ok_or("вкажіть IP або ім'я")?;
+    let ips: Vec<IpAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => {
+            let mut unique = Vec::new();
+            for addr in (host, 0).to_socket_addrs().map_err(|e| format!("{}: {}", host, e))? { if !unique.contains(&addr.ip()) { unique.push(addr.ip()); } }
+            unique
+        }
+    };
+    let server = resolver(cfg.dns_server.as_deref());
+    let timeout = Duration::from_millis(cfg.timeout_ms.max(1500));
+    let mut found = 0;
+    for (i, ip) in ips.iter().enumerate() {
+        let ptr = match reverse_lookup(server, *ip, timeout) {
+            Ok(names) if !names.is_empty() => { found += 1; names.join(", ") }
+            Ok(_) => "(немає PTR)".to_string(),
+            Err(e) if e == "NXDOMAIN" => "(немає PTR)".to_string(),
+            Err(e) => format!("⚠ {}", e),
+        };
+        row(i, vec![ip.to_string(), ptr]);
+    }
+    Ok(format!("{}: {} з {} адрес мають PTR", host, found, ips.len()))
+}
+
+// Обхід мережі: хост знайдено, якщо відкритий хоч один порт. Порівнюємо з config.targets:
+// нові хости, відомі цілі, і цілі з цієї мережі, що не відповіли (зниклі)
+fn discover(input: &str, cfg: &DiagConfig, targets: &[Target], cancel: &AtomicBool, row: &(dyn Fn(usize, Vec<String>) + Sync)) -> Result<String, String> {
+    let mut words = input.split_whitespace();
+    let range = words.next().ok_or("вкажіть мережу, напр. 192.168.1.0/24")?;
+    let spec: Vec<&str> = words.collect();
+    let ports = if spec.is_empty() { DISCOVERY_PORTS.to_vec() } else { parse_ports(&spec.join(","))? };
+    let hosts = parse_cidr(range)?;
+    let in_range: HashSet<Ipv4Addr> = hosts.iter().copied().collect();
+
+    // Відомі цілі з цієї мережі перевіряємо і на їхньому власному порту, щоб не вважати їх зниклими помилково
+    let known: Vec<(Ipv4Addr, u16, &str)> = targets.iter().filter_map(|t| {
+        let (host, port) = split_address(&t.address);
+        let IpAddr::V4(ip) = resolve_host(&host).ok()? else { return None };
+        in_range.contains(&ip).then(|| (ip, port.parse().unwrap_or(0), t.name.as_str()))
+    }).collect();
+    let mut probes: Vec<(Ipv4Addr, u16)> = hosts.iter().flat_map(|h| ports.iter().map(move |p| (*h, *p))).collect();
+    for (ip, port, _) in &known { if *port != 0 && !probes.contains(&(*ip, *port)) { probes.push((*ip, *port)); } }
+
+    let status = |ip: Ipv4Addr| -> String {
+        let names: Vec<&str> = known.iter().filter(|k| k.0 == ip).map(|k| k.2).collect();
+        if names.is_empty() { NEW_HOST.to_string() } else { format!("✓ {}", names.join(", ")) }
+    };
+    let found = sweep(&probes, cfg, cancel, &|ip, name, open| {
+        row(u32::from(ip) as usize, vec![ip.to_string(), name.to_string(), open.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "), status(ip)]);
+    });
+
+    let new = found.keys().filter(|ip| !known.iter().any(|k| k.0 == **ip)).count();
+    let mut gone = 0;
+    for (ip, _, name) in known.iter().filter(|k| !found.contains_key(&k.0)) {
+        gone += 1;
+        row(u32::from(*ip) as usize, vec![ip.to_string(), String::new(), String::new(), format!("✗ {} не відповідає", name)]);
+    }
+    Ok(format!("{}: знайдено {} хостів ({} нових, {} зниклих цілей) з {} адрес", range, found.len(), new, gone, hosts.len()))
+}
+
+// Паралельна перевірка пар (IP, порт); для кожного знайденого хоста — один PTR-запит.
+// `on_found` викликається при кожній зміні хоста (новий порт, ім'я). Повертає IP -> (PTR, відкриті порти)
+pub fn sweep(probes: &[(Ipv4Addr, u16)], cfg: &DiagConfig, cancel: &AtomicBool, on_found: &(dyn Fn(Ipv4Addr, &str, &[u16]) + Sync)) -> BTreeMap<Ipv4Addr, (String, Vec<u16>)> {
+    let dns_server = resolver(cfg.dns_server.as_deref());
+    let timeout = Duration::from_millis(cfg.timeout_ms);
+    let next = AtomicUsize::new(0);
+    let found: Mutex<BTreeMap<Ipv4Addr, (String, Vec<u16>)>> = Mutex::new(BTreeMap::new());
+    thread::scope(|scope| {
+        for _ in 0..cfg.scan_concurrency.clamp(1, 1000).min(probes.len()) {
+            scope.spawn(|| {
+                while !cancel.load(Ordering::Relaxed) {
+                    let Some(&(ip, port)) = probes.get(next.fetch_add(1, Ordering::Relaxed)) else { break };
+                    if TcpStream::connect_timeout(&SocketAddr::new(IpAddr::V4(ip), port), timeout).is_err() { continue; }
+                    let first = {
+                        let mut found = found.lock().unwrap();
+                        let (name, open) = found.entry(ip).or_default();
+                        open.push(port);
+                        open.sort_unstable();
+                        on_found(ip, name, open);
+                        open.len() == 1
+                    };
+                    // PTR — лише раз на хост, поза блокуванням
+                    if first {
+                        let ptr = reverse_lookup(dns_server, IpAddr::V4(ip), Duration::from_millis(1000)).ok().and_then(|n| n.into_iter().next()).unwrap_or_default();
+                        let mut found = found.lock().unwrap();
+                        let (name, open) = found.entry(ip).or_default();
+                        *name = ptr;
+                        on_found(ip, name, open);
+                    }
+                }
+            });
+        }
+    });
+    found.into_inner().unwrap_or_default()
+}
+
+enum Probe {
+    Reached(Duration),
+    Hop(Option<IpAddr>, Duration), // ICMP від проміжного вузла (адреса — лише на Linux)
+    Lost,
+}
+
+#[derive(Default)]
+struct HopStats {
+    addr: Option<IpAddr>,
+    name: String,
+    sent: usize,
+    rtts: Vec<f64>,
+}
+
+impl HopStats {
+    fn cells(&self, ttl: u32) -> Vec<String> {
+        let ms = |v: f64| format!("{:.1}", v);
+        let loss = 100.0 * (self.sent - self.rtts.len()) as f64 / self.sent.max(1) as f64;
+        let (last, avg, best, worst) = if self.rtts.is_empty() { Default::default() } else {
+            let avg = self.rtts.iter().sum::<f64>() / self.rtts.len() as f64;
+            let best = self.rtts.iter().cloned().fold(f64::MAX, f64::min);
+            let worst = self.rtts.iter().cloned().fold(0.0, f64::max);
+            (ms(*self.rtts.last().unwrap_or(&0.0)), ms(avg), ms(best), ms(worst))
+        };
+        let addr = match (self.addr, self.rtts.is_empty()) { (Some(a), _) => a.to_string(), (None, true) => "*".to_string(), (None, false) => "?".to_string() };
+        vec![ttl.to_string(), addr, self.name.clone(), format!("{:.0}%", loss), last, avg, best, worst]
+    }
+}
+
+// TCP SYN з TTL = 1, 2, ...: проміжні вузли відповідають ICMP Time Exceeded, ціль — SYN-ACK або RST.
+// Кожен hop пробуємо кілька разів, як mtr: втрати й час у таблиці
+fn trace(input: &str, cfg: &DiagConfig, cancel: &AtomicBool, row: &dyn Fn(usize, Vec<String>)) -> Result<String, String> {
+    let target = input.split_whitespace().next().ok_or("вкажіть хост")?;
+    let (host, port) = split_address(target);
+    let port: u16 = if port.is_empty() { 80 } else { port.parse().map_err(|_| format!("некоректний порт у '{}'", target))? };
+    let dst = SocketAddr::new(resolve_host(&host)?, port);
+    let timeout = Duration::from_millis(cfg.timeout_ms.max(500));
+    let dns_server = resolver(cfg.dns_server.as_deref());
+    let max_hops = cfg.max_hops.clamp(1, 64);
+
+    for ttl in 1..=max_hops {
+        let mut stats = HopStats::default();
+        let mut reached = false;
+        for _ in 0..TRACE_ROUNDS {
+            if cancel.load(Ordering::Relaxed) { return Ok(format!("{} — зупинено на hop {}", dst, ttl)); }
+            stats.sent += 1;
+            match probe(dst, ttl, timeout) {
+                Probe::Reached(rtt) => { reached = true; stats.addr = Some(dst.ip()); stats.rtts.push(rtt.as_secs_f64() * 1000.0); }
+                Probe::Hop(addr, rtt) => { stats.addr = addr.or(stats.addr); stats.rtts.push(rtt.as_secs_f64() * 1000.0); }
+                Probe::Lost => {}
+            }
+            row(ttl as usize, stats.cells(ttl));
+        }
+        if let Some(addr) = stats.addr {
+            stats.name = reverse_lookup(dns_server, addr, Duration::from_millis(1000)).ok().and_then(|n| n.into_iter().next()).unwrap_or_default();
+            row(ttl as usize, stats.cells(ttl));
+        }
+        if reached { return Ok(format!("{} досягнуто за {} hop", dst, ttl)); }
+    }
+    Ok(format!("{} не досягнуто за {} hop", dst, max_hops))
+}
+
+fn probe(dst: SocketAddr, ttl: u32, timeout: Duration) -> Probe {
+    let Ok(socket) = Socket::new(Domain::for_address(dst), Type::STREAM, Some(Protocol::TCP)) else { return Probe::Lost };
+    let ttl_set = if dst.is_ipv4() { socket.set_ttl(ttl) } else { socket.set_unicast_hops_v6(ttl) };
+    if ttl_set.is_err() { return Probe::Lost; }
+    #[cfg(target_os = "linux")]
+    linux::enable_recverr(&socket, dst.is_ipv4());
+    let start = Instant::now();
+    match socket.connect_timeout(&dst.into(), timeout) {
+        Ok(()) => Probe::Reached(start.elapsed()),
+        // RST від самої цілі: порт закритий, але хост досягнуто
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => Probe::Reached(start.elapsed()),
+        Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => Probe::Lost,
+        Err(_) => Probe::Hop(icmp_offender(&socket), start.elapsed()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn icmp_offender(socket: &Socket) -> Option<IpAddr> {
+    linux::icmp_offender(socket)
+}
+
+// Без IP_RECVERR адресу вузла, що відповів ICMP, через звичайний TCP-сокет не дізнатись
+#[cfg(not(target_os = "linux"))]
+fn icmp_offender(_socket: &Socket) -> Option<IpAddr> {
+    None
+}
+
+// IP_RECVERR: ICMP-помилки для TCP-сокета потрапляють у чергу помилок разом з адресою відправника
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        mem,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        os::fd::AsRawFd,
+        ptr,
+    };
+    use socket2::Socket;
+
+    pub fn enable_recverr(socket: &Socket, v4: bool) {
+        let on: libc::c_int = 1;
+        let (level, name) = if v4 { (libc::IPPROTO_IP, libc::IP_RECVERR) } else { (libc::IPPROTO_IPV6, libc::IPV6_RECVERR) };
+        // SAFETY: передаємо вказівник на живий c_int і його точний розмір
+        unsafe { libc::setsockopt(socket.as_raw_fd(), level, name, &on as *const _ as *const libc::c_void, mem::size_of_val(&on) as libc::socklen_t); }
+    }
+
+    pub fn icmp_offender(socket: &Socket) -> Option<IpAddr> {
+        let mut data = [0u8; 256];
+        let mut control = [0u8; 512];
+        let mut iov = libc::iovec { iov_base: data.as_mut_ptr() as *mut libc::c_void, iov_len: data.len() };
+        // SAFETY: msghdr — звичайна C-структура, нулі для неї коректні
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = control.len() as _;
+        // SAFETY: буфери живуть до кінця функції, розміри вказані в msghdr
+        if unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_ERRQUEUE | libc::MSG_DONTWAIT) } < 0 { return None; }
+
+        // SAFETY: обходимо cmsg лише макросами CMSG_*, що не виходять за msg_controllen
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let (level, kind) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            if (level == libc::IPPROTO_IP && kind == libc::IP_RECVERR) || (level == libc::IPPROTO_IPV6 && kind == libc::IPV6_RECVERR) {
+                // SAFETY: для IP_RECVERR дані — sock_extended_err, одразу за ним адреса відправника (SO_EE_OFFENDER)
+                unsafe {
+                    let err_ptr = libc::CMSG_DATA(cmsg) as *const libc::sock_extended_err;
+                    let err: libc::sock_extended_err = ptr::read_unaligned(err_ptr);
+                    if err.ee_origin != libc::SO_EE_ORIGIN_ICMP && err.ee_origin != libc::SO_EE_ORIGIN_ICMP6 { return None; }
+                    let offender = err_ptr.add(1) as *const u8;
+                    let family = ptr::read_unaligned(offender as *const libc::sa_family_t) as libc::c_int;
+                    return match family {
+                        libc::AF_INET => {
+                            let sin: libc::sockaddr_in = ptr::read_unaligned(offender as *const libc::sockaddr_in);
+                            Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr))))
+                        }
+                        libc::AF_INET6 => {
+                            let sin6: libc::sockaddr_in6 = ptr::read_unaligned(offender as *const libc::sockaddr_in6);
+                            Some(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)))
+                        }
+                        _ => None,
+                    };
+                }
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cidr_hosts() {
+        let ip = |s: &str| s.parse::<Ipv4Addr>().unwrap();
+        assert_eq!(parse_cidr("10.0.0.5/32").unwrap(), [ip("10.0.0.5")]);
+        assert_eq!(parse_cidr("10.0.0.5").unwrap(), [ip("10.0.0.5")]);
+        assert_eq!(parse_cidr("10.0.0.5/31").unwrap(), [ip("10.0.0.4"), ip("10.0.0.5")]);
+        let hosts = parse_cidr("192.168.1.77/24").unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!((hosts[0], hosts[253]), (ip("192.168.1.1"), ip("192.168.1.254")));
+        assert!(parse_cidr("10.0.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+    }
+}
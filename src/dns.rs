@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket},
+    sync::atomic::{AtomicU16, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Мінімальний DNS-клієнт (RFC 1035): UDP з EDNS0, повтор через TCP, якщо відповідь обрізано
+pub const TYPE_PTR: u16 = 12;
+
+// Типи, які показує "DNS lookup" (ANY сервери давно не віддають, тож питаємо кожен окремо)
+pub const LOOKUP_TYPES: [u16; 8] = [1, 28, 5, 15, 2, 16, 6, 257];
+const TYPE_SRV: u16 = 33;
+
+static NEXT_QUERY_ID: AtomicU16 = AtomicU16::new(0);
+
+#[derive(Clone, Debug)]
+pub struct DnsRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub ttl: u32,
+    pub data: String,
+}
+
+pub fn type_name(rtype: u16) -> String {
+    match rtype {
+        1 => "A", 2 => "NS", 5 => "CNAME", 6 => "SOA", 12 => "PTR", 15 => "MX", 16 => "TXT", 28 => "AAAA", 33 => "SRV", 257 => "CAA",
+        _ => return format!("TYPE{}", rtype),
+    }.to_string()
+}
+
+// Типи для імені: SRV лише для `_service._proto.domain`
+pub fn lookup_types(name: &str) -> Vec<u16> {
+    let mut types = LOOKUP_TYPES.to_vec();
+    if name.starts_with('_') { types.insert(0, TYPE_SRV); }
+    types
+}
+
+// Сервер з конфігу, інакше перший `nameserver` з /etc/resolv.conf, інакше 8.8.8.8
+pub fn resolver(configured: Option<&str>) -> SocketAddr {
+    let parse = |s: &str| -> Option<SocketAddr> {
+        let s = s.trim();
+        s.parse::<SocketAddr>().ok().or_else(|| s.split('%').next()?.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 53)))
+    };
+    if let Some(addr) = configured.and_then(parse) { return addr; }
+    let from_resolv_conf = fs::read_to_string("/etc/resolv.conf").ok().and_then(|text| {
+        text.lines().filter_map(|l| l.trim().strip_prefix("nameserver")).find_map(parse)
+    });
+    from_resolv_conf.unwrap_or_else(|| SocketAddr::from(([8, 8, 8, 8], 53)))
+}
+
+// 4.3.2.1.in-addr.arpa / ніббли у зворотному порядку для ip6.arpa
+pub fn reverse_name(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => { let o = v4.octets(); format!("{}.{}.{}.{}.in-addr.arpa", o[3], o[2], o[1], o[0]) }
+        IpAddr::V6(v6) => {
+            let nibbles: Vec<String> = v6.octets().iter().rev().flat_map(|b| [format!("{:x}", b & 0x0f), format!("{:x}", b >> 4)]).collect();
+            format!("{}.ip6.arpa", nibbles.join("."))
+        }
+    }
+}
+
+// Імена PTR для адреси (порожньо, якщо запису немає)
+pub fn reverse_lookup(server: SocketAddr, ip: IpAddr, timeout: Duration) -> Result<Vec<String>, String> {
+    Ok(query(server, &reverse_name(ip), TYPE_PTR, timeout)?.into_iter().filter(|r| r.rtype == TYPE_PTR).map(|r| r.data).collect())
+}
+
+// Один запит: записи з секції answer або текст помилки (NXDOMAIN, SERVFAIL, таймаут...)
+pub fn query(server: SocketAddr, name: &str, qtype: u16, timeout: Duration) -> Result<Vec<DnsRecord>, String> {
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos() as u16).unwrap_or(0);
+    let id = seed.wrapping_add(NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed));
+    let packet = build_query(id, name, qtype)?;
+
+    let response = query_udp(server, &packet, timeout)?;
+    let response = if response.len() > 2 && response[2] & 0x02 != 0 { query_tcp(server, &packet, timeout)? } else { response };
+    parse_response(&response, id)
+}
+
+fn query_udp(server: SocketAddr, packet: &[u8], timeout: Duration) -> Result<Vec<u8>, String> {
+    let bind: SocketAddr = if server.is_ipv4() { (Ipv4Addr::UNSPECIFIED, 0).into() } else { (Ipv6Addr::UNSPECIFIED, 0).into() };
+    let socket = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+    socket.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; 4096];
+    for _ in 0..2 {
+        socket.send_to(packet, server).map_err(|e| e.to_string())?;
+        // Чужі/запізнілі відповіді відкидаємо за id
+        while let Ok((n, from)) = socket.recv_from(&mut buf) {
+            if from == server && n >= 12 && buf[..2] == packet[..2] { return Ok(buf[..n].to_vec()); }
+        }
+    }
+    Err(format!("немає відповіді від {}", server))
+}
+
+fn query_tcp(server: SocketAddr, packet: &[u8], timeout: Duration) -> Result<Vec<u8>, String> {
+    let mut stream = TcpStream::connect_timeout(&server, timeout).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    let mut framed = (packet.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(packet);
+    stream.write_all(&framed).map_err(|e| e.to_string())?;
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).map_err(|e| e.to_string())?;
+    let mut response = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut response).map_err(|e| e.to_string())?;
+    Ok(response)
+}
+
+fn build_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(64);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x00]); // RD
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 1]); // 1 питання, 1 додатковий (OPT)
+    for label in name.trim_end_matches('.').split('.').filter(|l| !l.is_empty()) {
+        if label.len() > 63 { return Err(format!("задовга мітка '{}'", label)); }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&[0, 1]); // IN
+    // EDNS0: приймаємо UDP-відповіді до 4096 байтів
+    packet.extend_from_slice(&[0, 0, 41, 0x10, 0x00, 0, 0, 0, 0, 0, 0]);
+    Ok(packet)
+}
+
+fn parse_response(buf: &[u8], id: u16) -> Result<Vec<DnsRecord>, String> {
+    if buf.len() < 12 || u16::from_be_bytes([buf[0], buf[1]]) != id { return Err("некоректна відповідь".to_string()); }
+    match buf[3] & 0x0f {
+        0 => {}
+        2 => return Err("SERVFAIL".to_string()),
+        3 => return Err("NXDOMAIN".to_string()),
+        5 => return Err("REFUSED".to_string()),
+        code => return Err(format!("RCODE {}", code)),
+    }
+    let questions = u16::from_be_bytes([buf[4], buf[5]]);
+    let answers = u16::from_be_bytes([buf[6], buf[7]]);
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(buf, pos)?.1 + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let (name, next) = read_name(buf, pos)?;
+        let header = buf.get(next..next + 10).ok_or("обрізаний запис")?;
+        let rtype = u16::from_be_bytes([header[0], header[1]]);
+        let ttl = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let len = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let start = next + 10;
+        if start + len > buf.len() { return Err("обрізаний запис".to_string()); }
+        records.push(DnsRecord { name, rtype, ttl, data: format_rdata(buf, rtype, start, len)? });
+        pos = start + len;
+    }
+    Ok(records)
+}
+
+// Ім'я з урахуванням стиснення (вказівники 0xC0); повертає ім'я і позицію після нього
+fn read_name(buf: &[u8], mut pos: usize) -> Result<(String, usize), String> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    for _ in 0..128 {
+        let len = *buf.get(pos).ok_or("обрізане ім'я")? as usize;
+        if len == 0 {
+            let name = if labels.is_empty() { ".".to_string() } else { labels.join(".") };
+            return Ok((name, end.unwrap_or(pos + 1)));
+        }
+        if len & 0xc0 == 0xc0 {
+            let low = *buf.get(pos + 1).ok_or("обрізане ім'я")? as usize;
+            end.get_or_insert(pos + 2);
+            pos = ((len & 0x3f) << 8) | low;
+            continue;
+        }
+        let label = buf.get(pos + 1..pos + 1 + len).ok_or("обрізане ім'я")?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        pos += 1 + len;
+    }
+    Err("зациклене стиснення імені".to_string())
+}
+
+fn format_rdata(buf: &[u8], rtype: u16, start: usize, len: usize) -> Result<String, String> {
+    let data = &buf[start..start + len];
+    let u16_at = |i: usize| -> Result<u16, String> { data.get(i..i + 2).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or_else(|| "обрізані дані".to_string()) };
+    Ok(match rtype {
+        1 if len == 4 => Ipv4Addr::new(data[0], data[1], data[2], data[3]).to_string(),
+        28 if len == 16 => { let mut o = [0u8; 16]; o.copy_from_slice(data); Ipv6Addr::from(o).to_string() }
+        2 | 5 | 12 => read_name(buf, start)?.0,
+        15 => format!("{} {}", u16_at(0)?, read_name(buf, start + 2)?.0),
+        16 => {
+            let mut parts = Vec::new();
+            let mut i = 0;
+            while i < len {
+                let n = data[i] as usize;
+                let chunk = data.get(i + 1..i + 1 + n).ok_or("обрізаний TXT")?;
+                parts.push(format!("\"{}\"", String::from_utf8_lossy(chunk)));
+                i += 1 + n;
+            }
+            parts.join(" ")
+        }
+        6 => {
+            let (mname, p) = read_name(buf, start)?;
+            let (rname, p) = read_name(buf, p)?;
+            let nums = &buf.get(p..p + 20).ok_or("обрізаний SOA")?;
+            let n = |i: usize| u32::from_be_bytes([nums[i], nums[i + 1], nums[i + 2], nums[i + 3]]);
+            format!("{} {} serial={} refresh={} retry={} expire={} min={}", mname, rname, n(0), n(4), n(8), n(12), n(16))
+        }
+        33 => format!("{} {} {} {}", u16_at(0)?, u16_at(2)?, u16_at(4)?, read_name(buf, start + 6)?.0),
+        257 if len >= 2 => {
+            let tag_len = data[1] as usize;
+            let tag = data.get(2..2 + tag_len).ok_or("обрізаний CAA")?;
+            format!("{} {} \"{}\"", data[0], String::from_utf8_lossy(tag), String::from_utf8_lossy(&data[2 + tag_len..]))
+        }
+        // Невідомий тип: сирі байти у форматі RFC 3597
+        _ => format!("\\# {} {}", len, data.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+    })
+}
@@ -0,0 +1,90 @@
+use std::{collections::HashMap, fs};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Job, JobStatus};
+
+const HISTORY_FILE: &str = "history.json";
+const MAX_ENTRIES: usize = 2000;
+const EXCERPT_LINES: usize = 20;
+
+// Один завершений запуск: що саме виконали і чим скінчилось
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: String, // "YYYY-MM-DD HH:MM:SS"
+    pub command: String,   // Назва команди з config.json
+    pub name: String,      // Як job називався у вкладці Jobs
+    pub argv: Vec<String>,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    #[serde(default)]
+    pub target: Option<String>,
+    pub exit: Option<i32>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub excerpt: Vec<String>, // Останні рядки виводу
+}
+
+impl HistoryEntry {
+    pub fn from_job(job: &Job) -> Self {
+        let (exit, error) = match &job.status {
+            JobStatus::Exited(code) => (Some(*code), None),
+            JobStatus::Failed(e) => (None, Some(e.clone())),
+            JobStatus::Running => (None, None),
+        };
+        let mut argv = vec![job.spec.program.clone()];
+        argv.extend(job.spec.args.iter().cloned());
+        HistoryEntry {
+            timestamp: job.started.format("%Y-%m-%d %H:%M:%S").to_string(),
+            command: job.spec.command.clone(),
+            name: job.name.clone(),
+            argv,
+            values: job.spec.values.clone(),
+            target: job.target.clone(),
+            exit,
+            error,
+            duration_ms: job.duration.map(|d| d.as_millis() as u64).unwrap_or(0),
+            excerpt: job.output.iter().skip(job.output.len().saturating_sub(EXCERPT_LINES)).cloned().collect(),
+        }
+    }
+
+    pub fn ok(&self) -> bool {
+        self.exit == Some(0)
+    }
+}
+
+pub fn load_history() -> Vec<HistoryEntry> {
+    fs::read_to_string(HISTORY_FILE).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default()
+}
+
+// Додаємо запис і одразу зберігаємо: історія не повинна губитись при аварійному виході
+pub fn record(history: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    history.push(entry);
+    if history.len() > MAX_ENTRIES { history.drain(..history.len() - MAX_ENTRIES); }
+    let _ = fs::write(HISTORY_FILE, serde_json::to_string_pretty(history).unwrap_or_default());
+}
+
+// Індекси записів, що підходять під фільтр (підрядок без регістру), найновіші першими
+pub fn filter_history(history: &[HistoryEntry], filter: &str) -> Vec<usize> {
+    let needle = filter.trim().to_lowercase();
+    history.iter().enumerate().rev()
+        .filter(|(_, e)| {
+            if needle.is_empty() { return true; }
+            let haystack = format!("{} {} {} {} {}", e.timestamp, e.name, e.argv.join(" "), e.target.as_deref().unwrap_or(""), e.exit.map(|c| format!("exit {}", c)).unwrap_or_default());
+            haystack.to_lowercase().contains(&needle)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Попередні значення параметра команди, без повторів, найновіші першими
+pub fn recent_values(history: &[HistoryEntry], command: &str, param: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    for e in history.iter().rev().filter(|e| e.command == command) {
+        if let Some(v) = e.values.get(param) {
+            if !v.is_empty() && !seen.contains(v) { seen.push(v.clone()); }
+        }
+    }
+    seen
+}
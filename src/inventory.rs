@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, fs, net::Ipv4Addr, sync::{atomic::AtomicBool, mpsc::Sender}, thread, time::{Duration, Instant}};
+use chrono::Local;
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{DiagConfig, DiscoveryConfig};
+use crate::diag::{parse_cidr, service_name, sweep};
+use crate::types::AppEvent;
+
+const INVENTORY_FILE: &str = "inventory.json";
+const NOTIFY_LINES: usize = 5;
+
+// Що відомо про адресу за всі сканування
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct InventoryHost {
+    #[serde(default)]
+    pub name: String, // PTR
+    #[serde(default)]
+    pub ports: Vec<u16>, // Відкриті на момент останнього сканування
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+pub type Inventory = BTreeMap<Ipv4Addr, InventoryHost>;
+
+#[derive(Debug, PartialEq)]
+pub enum InventoryChange {
+    NewHost { ip: Ipv4Addr, name: String, ports: Vec<u16> },
+    PortOpened { ip: Ipv4Addr, name: String, port: u16 },
+    PortClosed { ip: Ipv4Addr, name: String, port: u16 },
+}
+
+impl InventoryChange {
+    pub fn describe(&self) -> String {
+        let host = |ip: &Ipv4Addr, name: &str| if name.is_empty() { ip.to_string() } else { format!("{} ({})", ip, name) };
+        let port_label = |port: &u16| match service_name(*port) { "" => port.to_string(), service => format!("{}/{}", port, service) };
+        match self {
+            InventoryChange::NewHost { ip, name, ports } => format!("new host {}, ports {}", host(ip, name), ports.iter().map(port_label).collect::<Vec<_>>().join(", ")),
+            InventoryChange::PortOpened { ip, name, port } => format!("{}: port {} opened", host(ip, name), port_label(port)),
+            InventoryChange::PortClosed { ip, name, port } => format!("{}: port {} closed", host(ip, name), port_label(port)),
+        }
+    }
+}
+
+pub fn load_inventory() -> Inventory {
+    fs::read_to_string(INVENTORY_FILE).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default()
+}
+
+fn save_inventory(inventory: &Inventory) {
+    let _ = fs::write(INVENTORY_FILE, serde_json::to_string_pretty(inventory).unwrap_or_default());
+}
+
+// Оновлює знімок результатами сканування і повертає зміни.
+// Порти поза `ports` не чіпаємо: їх у цьому скануванні просто не перевіряли.
+// Хост, що зник, лишається в знімку без портів — повернення буде "port opened", а не "new host"
+pub fn apply_scan(inventory: &mut Inventory, hosts: &[Ipv4Addr], ports: &[u16], found: &BTreeMap<Ipv4Addr, (String, Vec<u16>)>, now: &str) -> Vec<InventoryChange> {
+    let mut changes = Vec::new();
+    for ip in hosts {
+        let scanned = found.get(ip);
+        let Some(host) = inventory.get_mut(ip) else {
+            if let Some((name, open)) = scanned {
+                changes.push(InventoryChange::NewHost { ip: *ip, name: name.clone(), ports: open.clone() });
+                inventory.insert(*ip, InventoryHost { name: name.clone(), ports: open.clone(), first_seen: now.to_string(), last_seen: now.to_string() });
+            }
+            continue;
+        };
+        let open: &[u16] = scanned.map(|(_, o)| o.as_slice()).unwrap_or_default();
+        if let Some((name, _)) = scanned.filter(|(n, _)| !n.is_empty()) { host.name = name.clone(); }
+        for port in open.iter().filter(|p| !host.ports.contains(p)) {
+            changes.push(InventoryChange::PortOpened { ip: *ip, name: host.name.clone(), port: *port });
+        }
+        for port in host.ports.iter().filter(|p| ports.contains(p) && !open.contains(p)) {
+            changes.push(InventoryChange::PortClosed { ip: *ip, name: host.name.clone(), port: *port });
+        }
+        host.ports.retain(|p| !ports.contains(p));
+        host.ports.extend_from_slice(open);
+        host.ports.sort_unstable();
+        if scanned.is_some() { host.last_seen = now.to_string(); }
+    }
+    changes
+}
+
+// Фоновий потік: сканує мережі з `discovery.networks` кожні `interval_mins`.
+// Перше сканування мережі — лише знімок (baseline), без сповіщень
+pub fn start_discovery(cfg: DiscoveryConfig, diag: DiagConfig, tx: Sender<AppEvent>) {
+    if cfg.networks.is_empty() { return; }
+    thread::spawn(move || {
+        let never = AtomicBool::new(false);
+        loop {
+            let mut inventory = load_inventory();
+            for network in &cfg.networks {
+                let timestamp = Local::now().format("%H:%M:%S");
+                let hosts = match parse_cidr(network) {
+                    Ok(hosts) => hosts,
+                    Err(e) => { let _ = tx.send(AppEvent::LogOutput(format!("[{}] 🛰 NET {} skipped: {}", timestamp, network, e))); continue; }
+                };
+                let probes: Vec<(Ipv4Addr, u16)> = hosts.iter().flat_map(|h| cfg.ports.iter().map(move |p| (*h, *p))).collect();
+                let started = Instant::now();
+                let found = sweep(&probes, &diag, &never, &|_, _, _| {});
+                let baseline = !hosts.iter().any(|ip| inventory.contains_key(ip));
+                let changes = apply_scan(&mut inventory, &hosts, &cfg.ports, &found, &Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+
+                let timestamp = Local::now().format("%H:%M:%S");
+                if baseline {
+                    let _ = tx.send(AppEvent::LogOutput(format!("[{}] 🛰 NET {}: baseline inventory, {} host(s) in {:.0}s", timestamp, network, found.len(), started.elapsed().as_secs_f32())));
+                    continue;
+                }
+                for change in &changes { let _ = tx.send(AppEvent::LogOutput(format!("[{}] 🛰 NET {}: {}", timestamp, network, change.describe()))); }
+                if !changes.is_empty() {
+                    let mut body: Vec<String> = changes.iter().take(NOTIFY_LINES).map(|c| c.describe()).collect();
+                    if changes.len() > NOTIFY_LINES { body.push(format!("… ще {}", changes.len() - NOTIFY_LINES)); }
+                    Notification::new()
+                        .summary(&format!("NETWORK CHANGE 🛰 {}", network))
+                        .body(&body.join("\n"))
+                        .appname("Admin Console")
+                        .show()
+                        .ok();
+                }
+            }
+            save_inventory(&inventory);
+            thread::sleep(Duration::from_secs(cfg.interval_mins.max(1) * 60));
+        }
+    });
+}
@@ -0,0 +1,358 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::Instant,
+};
+use encoding_rs::{Encoding, IBM866, UTF_8};
+
+use crate::config::{AdminCommand, Availability, KnownHosts, SshSettings, Target};
+use crate::types::{AppEvent, JobStatus};
+use crate::utils::{expand_env_vars, expand_template, target_values};
+
+// Лічильник id спільний для всіх потоків (UI, монітор, ранбуки)
+static NEXT_JOB_ID: AtomicUsize = AtomicUsize::new(1);
+static NEXT_BATCH_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub fn next_job_id() -> usize {
+    NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+pub fn next_batch_id() -> usize {
+    NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+// Що саме запускаємо: вже розгорнутий argv
+#[derive(Clone)]
+pub struct JobSpec {
+    pub command: String, // Назва команди з config.json (для історії й повтору)
+    pub values: HashMap<String, String>, // Значення, з яких розгорнуто argv (без `{selection}`)
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub encoding: String, // Мітка encoding_rs ("utf-8", "ibm866", "windows-1251") або "auto"
+    pub target: Option<String>, // Сервер, для якого розгорнуто `{target.*}`
+    pub batch: Option<usize>,   // Спільний id для запуску на кількох серверах
+    pub cwd: Option<String>,
+    pub env: Vec<(String, String)>,
+    pub clear_env: bool,
+    pub stdin: Option<String>,
+}
+
+impl JobSpec {
+    // Рядок для показу й копіювання; екранування як для sh, щоб у вікні Confirm було видно точний argv
+    pub fn command_line(&self) -> String {
+        std::iter::once(&self.program).chain(self.args.iter()).map(|a| sh_quote(a)).collect::<Vec<_>>().join(" ")
+    }
+
+    // Для dry-run: рядок запуску + робоча тека, імена змінних (без значень!) і розмір stdin
+    pub fn describe(&self) -> String {
+        let mut text = self.command_line();
+        if let Some(cwd) = &self.cwd { text.push_str(&format!("  [cwd: {}]", cwd)); }
+        if !self.env.is_empty() || self.clear_env {
+            let names: Vec<&str> = self.env.iter().map(|(k, _)| k.as_str()).collect();
+            text.push_str(&format!("  [env{}: {}]", if self.clear_env { " (clean)" } else { "" }, names.join(", ")));
+        }
+        if let Some(input) = &self.stdin { text.push_str(&format!("  [stdin: {} bytes]", input.len())); }
+        text
+    }
+}
+
+// Розгортає плейсхолдери команди у готовий argv
+pub fn build_spec(cmd: &AdminCommand, values: &HashMap<String, String>) -> JobSpec {
+    let stdin = if cmd.stdin_from_selection { values.get("selection").cloned() }
+        else { cmd.stdin.as_ref().map(|t| expand_template(t, values)) };
+    let mut env: Vec<(String, String)> = cmd.env.iter().map(|(k, v)| (k.clone(), expand_env_vars(&expand_template(v, values)))).collect();
+    env.sort();
+    let mut recorded = values.clone();
+    recorded.remove("selection");
+    let program = expand_template(&cmd.cmd, values);
+    let args: Vec<String> = cmd.args.iter().map(|a| expand_template(a, values)).collect();
+    let (program, args, cwd) = match &cmd.ssh {
+        Some(ssh) => {
+            let remote_cwd = cmd.cwd.as_ref().map(|c| expand_template(c, values));
+            let (p, a) = ssh_argv(ssh, &program, &args, remote_cwd.as_deref(), &env, cmd.clear_env, values);
+            (p, a, None)
+        }
+        None => (program, args, cmd.cwd.as_ref().map(|c| expand_env_vars(&expand_template(c, values)))),
+    };
+    JobSpec {
+        command: cmd.name.clone(),
+        values: recorded,
+        name: cmd.name.clone(),
+        program,
+        args,
+        encoding: cmd.encoding.clone(),
+        target: None,
+        batch: None,
+        cwd,
+        env,
+        clear_env: cmd.clear_env && cmd.ssh.is_none(), // Для ssh очищення робить `env -i` на хості
+        stdin,
+    }
+}
+
+// argv для `ssh`: опції, потім `--` і хост (щоб хост з форми не став опцією), потім віддалена команда.
+// Аргументи екрануємо для віддаленого sh, бо ssh склеює їх в один рядок; `env` передаємо через `env K=V`
+fn ssh_argv(ssh: &SshSettings, program: &str, args: &[String], remote_cwd: Option<&str>, env: &[(String, String)], clear_env: bool, values: &HashMap<String, String>) -> (String, Vec<String>) {
+    let mut argv: Vec<String> = vec!["-T".into(), "-o".into(), "BatchMode=yes".into(), "-o".into(), format!("ConnectTimeout={}", ssh.connect_timeout)];
+    let strict = match ssh.known_hosts { KnownHosts::Strict => "yes", KnownHosts::AcceptNew => "accept-new", KnownHosts::Off => "no" };
+    argv.extend(["-o".to_string(), format!("StrictHostKeyChecking={}", strict)]);
+    match (&ssh.known_hosts_file, ssh.known_hosts) {
+        (Some(file), _) => argv.extend(["-o".to_string(), format!("UserKnownHostsFile={}", expand_env_vars(file))]),
+        (None, KnownHosts::Off) => argv.extend(["-o".to_string(), format!("UserKnownHostsFile={}", if cfg!(windows) { "NUL" } else { "/dev/null" })]),
+        _ => {}
+    }
+    if let Some(port) = ssh.port { argv.extend(["-p".to_string(), port.to_string()]); }
+    if let Some(key) = &ssh.key { argv.extend(["-i".to_string(), expand_env_vars(key), "-o".to_string(), "IdentitiesOnly=yes".to_string()]); }
+    if let Some(user) = &ssh.user { argv.extend(["-l".to_string(), expand_template(user, values)]); }
+    argv.push("--".into());
+    argv.push(expand_template(&ssh.host, values));
+
+    let mut remote: Vec<String> = Vec::new();
+    if let Some(dir) = remote_cwd { remote.push(format!("cd {} &&", sh_quote(dir))); }
+    if !env.is_empty() || clear_env {
+        remote.push("env".to_string());
+        if clear_env { remote.push("-i".to_string()); }
+        remote.extend(env.iter().map(|(k, v)| sh_quote(&format!("{}={}", k, v))));
+    }
+    remote.extend(std::iter::once(program).chain(args.iter().map(|a| a.as_str())).map(sh_quote));
+    argv.push(remote.join(" "));
+    ("ssh".to_string(), argv)
+}
+
+// Екранування для POSIX sh: безпечні рядки як є, решта в одинарних лапках
+fn sh_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+%".contains(c)) { arg.to_string() }
+    else { format!("'{}'", arg.replace('\'', "'\\''")) }
+}
+
+// По одному spec на кожен сервер (або один, якщо серверів не обрано)
+pub fn specs_for_targets(cmd: &AdminCommand, values: &HashMap<String, String>, targets: &[Target]) -> Vec<JobSpec> {
+    if targets.is_empty() { return vec![build_spec(cmd, values)]; }
+    targets.iter().map(|target| {
+        let mut target_values_map = values.clone();
+        target_values_map.extend(target_values("target", target));
+        let mut spec = build_spec(cmd, &target_values_map);
+        spec.name = format!("{} @ {}", cmd.name, target.name);
+        spec.target = Some(target.name.clone());
+        spec
+    }).collect()
+}
+
+// Запуск команди на кожному обраному сервері паралельно (кожен job у своєму потоці).
+// Повертає id першого job та id пакета, якщо серверів більше одного
+pub fn spawn_for_targets(cmd: &AdminCommand, values: &HashMap<String, String>, targets: &[Target], tx: &Sender<AppEvent>) -> (usize, Option<usize>) {
+    let batch = if targets.len() > 1 { Some(next_batch_id()) } else { None };
+    let mut first_id = 0;
+    for (i, mut spec) in specs_for_targets(cmd, values, targets).into_iter().enumerate() {
+        spec.batch = batch;
+        let id = spawn_job(spec, tx.clone());
+        if i == 0 { first_id = id; }
+    }
+    (first_id, batch)
+}
+
+// Фоновий запуск команди з конфігу за назвою без людини (розклад, хуки монітора, завдання з run:).
+// `on_done(id, spec, status)` викликається з потоку job після завершення; Err — команди немає, вона недоступна
+// або потребує підтвердження (confirm / dry_run / danger_level — лише через вікно Confirm)
+pub fn spawn_named<F>(command: &str, label: &str, values: &HashMap<String, String>, target: Option<&Target>, commands: &[AdminCommand], tx: &Sender<AppEvent>, on_done: F) -> Result<usize, String>
+where
+    F: FnOnce(usize, &JobSpec, &JobStatus) + Send + 'static,
+{
+    let Some(cmd) = commands.iter().find(|c| c.name == command) else { return Err(format!("немає команди '{}'", command)); };
+    if cmd.availability != Availability::Available { return Err(format!("'{}' недоступна на цій ОС", cmd.name)); }
+    if cmd.needs_confirmation() { return Err(format!("'{}' потребує підтвердження", cmd.name)); }
+
+    let mut values = values.clone();
+    if let Some(target) = target { values.extend(target_values("target", target)); }
+    let mut spec = build_spec(cmd, &values);
+    spec.name = match target { Some(t) => format!("{} {} @ {}", label, cmd.name, t.name), None => format!("{} {}", label, cmd.name) };
+    spec.target = target.map(|t| t.name.clone());
+
+    let id = next_job_id();
+    let tx = tx.clone();
+    thread::spawn(move || {
+        let (status, _) = run_job(id, &spec, &tx);
+        on_done(id, &spec, &status);
+    });
+    Ok(id)
+}
+
+// Коротка причина неуспіху для логів і сповіщень
+pub fn failure_reason(status: &JobStatus) -> String {
+    match status {
+        JobStatus::Exited(code) => format!("exit {}", code),
+        JobStatus::Failed(e) => e.clone(),
+        JobStatus::Running => "running".to_string(),
+    }
+}
+
+// Запуск у фоні. Повертає id, під яким job з'явиться у вкладці Jobs
+pub fn spawn_job(spec: JobSpec, tx: Sender<AppEvent>) -> usize {
+    let id = next_job_id();
+    thread::spawn(move || { run_job(id, &spec, &tx); });
+    id
+}
+
+// Блокуючий запуск: рядки виводу летять у UI по мірі появи.
+// Повертає статус і stdout (для передачі між кроками ранбука)
+pub fn run_job(id: usize, spec: &JobSpec, tx: &Sender<AppEvent>) -> (JobStatus, Vec<String>) {
+    let _ = tx.send(AppEvent::JobStarted { id, spec: Box::new(spec.clone()) });
+    let start = Instant::now();
+    if !is_known_encoding(&spec.encoding) {
+        let _ = tx.send(AppEvent::JobOutput(id, format!("[encoding] Невідоме кодування '{}', використовую auto", spec.encoding)));
+    }
+
+    let mut command = Command::new(&spec.program);
+    command.args(&spec.args)
+        .stdin(if spec.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = &spec.cwd { command.current_dir(cwd); }
+    if spec.clear_env { command.env_clear(); }
+    command.envs(spec.env.iter().map(|(k, v)| (k, v)));
+
+    let mut stdout_lines = Vec::new();
+    let status = match command.spawn() {
+        Ok(mut child) => {
+            // Пишемо stdin окремим потоком, щоб не заблокуватись на заповненому stdout
+            if let (Some(mut pipe), Some(input)) = (child.stdin.take(), spec.stdin.clone()) {
+                thread::spawn(move || { let _ = pipe.write_all(input.as_bytes()); });
+            }
+            let stderr_reader = child.stderr.take().map(|err| {
+                let tx_err = tx.clone();
+                let encoding = spec.encoding.clone();
+                thread::spawn(move || stream_lines(id, err, "[stderr] ", &encoding, &tx_err))
+            });
+            if let Some(out) = child.stdout.take() { stdout_lines = stream_lines(id, out, "", &spec.encoding, tx); }
+            if let Some(handle) = stderr_reader { let _ = handle.join(); }
+
+            match child.wait() {
+                Ok(s) => match s.code() {
+                    Some(code) => JobStatus::Exited(code),
+                    None => JobStatus::Failed("terminated by signal".to_string()),
+                },
+                Err(e) => JobStatus::Failed(e.to_string()),
+            }
+        }
+        Err(e) => JobStatus::Failed(format!("Failed to run: {}", e)),
+    };
+
+    let _ = tx.send(AppEvent::JobFinished { id, status: status.clone(), duration: start.elapsed() });
+    (status, stdout_lines)
+}
+
+pub fn is_known_encoding(label: &str) -> bool {
+    let label = label.trim();
+    label.is_empty() || label.eq_ignore_ascii_case("auto") || Encoding::for_label(label.as_bytes()).is_some()
+}
+
+// Кодування консолі за замовчуванням: OEM-866 на Windows, UTF-8 деінде
+fn platform_default_encoding() -> &'static Encoding {
+    if cfg!(windows) { IBM866 } else { UTF_8 }
+}
+
+// Вибір кодування по першому шматку потоку: BOM -> валідний UTF-8 -> платформа
+fn resolve_encoding(label: &str, first_chunk: &[u8]) -> &'static Encoding {
+    let label = label.trim();
+    if !label.eq_ignore_ascii_case("auto") {
+        if let Some(enc) = Encoding::for_label(label.as_bytes()) { return enc; }
+    }
+    if let Some((enc, _)) = Encoding::for_bom(first_chunk) { return enc; }
+    match std::str::from_utf8(first_chunk) {
+        Ok(_) => UTF_8,
+        // error_len() == None: шматок просто обрізано посеред символу, це ще UTF-8
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => platform_default_encoding(),
+    }
+}
+
+// Декодуємо потоково (а не по рядках байтів), щоб не ламати UTF-16 і багатобайтові символи
+fn stream_lines<R: Read>(id: usize, mut source: R, prefix: &str, encoding: &str, tx: &Sender<AppEvent>) -> Vec<String> {
+    let mut collected = Vec::new();
+    let mut decoder = None;
+    let mut buf = [0u8; 4096];
+    let mut pending = String::new();
+    loop {
+        let n = source.read(&mut buf).unwrap_or(0);
+        let last = n == 0;
+        let chunk = &buf[..n];
+        let decoder = decoder.get_or_insert_with(|| resolve_encoding(encoding, chunk).new_decoder());
+
+        let mut decoded = String::new();
+        decoded.reserve(decoder.max_utf8_buffer_length(n).unwrap_or(n * 3));
+        let _ = decoder.decode_to_string(chunk, &mut decoded, last);
+        pending.push_str(&decoded);
+
+        while let Some(pos) = pending.find('\n') {
+            let line: String = pending.drain(..=pos).collect();
+            let line = line.trim_end_matches(['\r', '\n']).to_string();
+            let _ = tx.send(AppEvent::JobOutput(id, format!("{}{}", prefix, line)));
+            collected.push(line);
+        }
+        if last {
+            if !pending.is_empty() {
+                let line = pending.trim_end_matches('\r').to_string();
+                let _ = tx.send(AppEvent::JobOutput(id, format!("{}{}", prefix, line)));
+                collected.push(line);
+            }
+            break;
+        }
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(json: &str) -> AdminCommand {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn ssh_argv_quotes_remote_args() {
+        let cmd = command(r#"{"name": "List", "cmd": "ls", "args": ["-la", "{dir}", "it's"], "ssh": {"user": "admin", "port": 2222}}"#);
+        let values = HashMap::from([("target.host".to_string(), "10.0.0.5".to_string()), ("dir".to_string(), "my dir".to_string())]);
+        let spec = build_spec(&cmd, &values);
+        assert_eq!(spec.program, "ssh");
+        assert_eq!(spec.args, [
+            "-T", "-o", "BatchMode=yes", "-o", "ConnectTimeout=10", "-o", "StrictHostKeyChecking=yes",
+            "-p", "2222", "-l", "admin", "--", "10.0.0.5",
+            "ls -la 'my dir' 'it'\\''s'",
+        ]);
+        assert_eq!(spec.cwd, None);
+    }
+
+    #[test]
+    fn ssh_argv_keeps_cwd_and_env_on_remote() {
+        let cmd = command(r#"{"name": "Deploy", "cmd": "./deploy.sh", "cwd": "/srv/my app", "env": {"MODE": "blue green", "LEVEL": "2"}, "clear_env": true, "ssh": {"host": "web1"}}"#);
+        let spec = build_spec(&cmd, &HashMap::new());
+        assert_eq!(spec.args.last().unwrap(), "cd '/srv/my app' && env -i LEVEL=2 'MODE=blue green' ./deploy.sh");
+        assert_eq!(spec.args[spec.args.len() - 2], "web1");
+        assert!(!spec.clear_env); // Локальний ssh зберігає оточення (агент, HOME)
+    }
+
+    #[test]
+    fn unattended_launch_refuses_guarded_commands() {
+        let (tx, _rx) = std::sync::mpsc::channel();
+        for json in [r#"{"name": "Wipe", "cmd": "true", "confirm": true}"#, r#"{"name": "Wipe", "cmd": "true", "dry_run": true}"#, r#"{"name": "Wipe", "cmd": "true", "danger_level": "caution"}"#] {
+            let commands = [command(json)];
+            assert!(spawn_named("Wipe", "⏱", &HashMap::new(), None, &commands, &tx, |_, _, _| {}).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn command_line_shows_exact_argv() {
+        let cmd = command(r#"{"name": "Echo", "cmd": "echo", "args": ["a b", "say \"hi\"", "C:\\tmp\\", "it's", "", "plain"]}"#);
+        let spec = build_spec(&cmd, &HashMap::new());
+        assert_eq!(spec.args, ["a b", "say \"hi\"", "C:\\tmp\\", "it's", "", "plain"]);
+        assert_eq!(spec.command_line(), r#"echo 'a b' 'say "hi"' 'C:\tmp\' 'it'\''s' '' plain"#);
+    }
+}
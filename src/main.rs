@@ -3,6 +3,18 @@ mod types;
 mod utils;
 mod monitor; // <--- Підключаємо модуль
 mod ui;      // <--- Підключаємо модуль
+mod jobs;
+mod runbook;
+mod scheduler;
+mod palette;
+mod history;
+mod connect;
+mod net;
+mod dns;
+mod diag;
+mod inventory;
+mod recurrence;
+mod reminders;
 
 use anyhow::Result;
 use crossterm::{
@@ -11,28 +23,42 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{prelude::*, widgets::{Block, Borders, ListState, TableState}, style::{Color, Style}};
-use std::{fs, io, process::Command, sync::mpsc, thread, time::{Duration, Instant}};
-use tui_textarea::{TextArea, CursorMove};
-use encoding_rs::IBM866;
+use std::{collections::{BTreeSet, HashMap}, fs, io, sync::{atomic::Ordering, mpsc}, time::{Duration, Instant}};
+use tui_textarea::{TextArea, CursorMove, Scrolling};
 use arboard::Clipboard;
 use chrono::Local;
 
-use crate::config::AppConfig;
-use crate::types::{ServerStatus, AppEvent, EditorMode, ActiveView, MonitorCommand, Task, WizardStep};
-use crate::utils::{is_valid_time, parse_tasks_from_text};
+use crate::config::{append_targets, AppConfig, Availability, CommandParam, ConnectKind, DangerLevel, ParamKind};
+use crate::types::{ServerStatus, AppEvent, Reminder, EditorMode, ActiveView, MonitorCommand, WizardStep, Job, JobStatus, PaletteAction, PendingLaunch, ConfirmAction, RunbookRun, StepState, DiagRun, DiagTool, DIAG_TOOLS};
+use crate::runbook::{runbook_danger, runbook_preview, start_runbook};
+use crate::palette::{build_items, load_usage, rank, record_usage};
+use crate::history::{filter_history, load_history, recent_values, record, HistoryEntry};
+use crate::connect::{default_kind, open_session};
+use crate::net::wake_target;
+use crate::diag::{diag_view, discovered_targets, resolve_input, rows_as_text, start_diag, tool_label, NEW_HOST};
+use crate::utils::{append_log_line, attach_run_result, complete_task, title_with_markers, ensure_task_ids, reopen_task, schedule_text, task_block, is_line_of_task, new_task_id, replace_lines, build_form, cycle_choice, form_values, format_job_summary, group_members, target_commands, target_groups, parse_tasks_from_text, select_next, select_prev, selected_text, split_address, unavailable_message, validate_param, wrap_next, wrap_prev};
+use crate::jobs::{failure_reason, spawn_for_targets, specs_for_targets};
 // Імпортуємо функції з нових файлів
 use crate::monitor::start_monitor;
+use crate::inventory::start_discovery;
+use crate::recurrence::{due_at, resolve_when, schedule_order};
+use crate::reminders::{dismiss, is_snoozed, load_dismissed, load_snoozes, snooze, take_due, DUE_FORMAT, SNOOZE_MINUTES};
 use crate::ui::draw;
 
 fn main() -> Result<()> {
     // --- ІНІЦІАЛІЗАЦІЯ ---
     let file_names = vec!["notes.txt", "todo.txt", "logs.txt"];
-    let titles = vec![" 1.Notes ", " 2.Todo ", " 3.Logs "];
+    let titles = vec![" 1.Notes ", " 2.Todo ", " 3.Logs ", " 4.Jobs ", " 5.Runbooks ", " 6.History ", " 7.Diag "];
 
     let mut textareas = Vec::new();
     for filename in &file_names {
         let content = fs::read_to_string(filename).unwrap_or_default();
-        let mut ta = TextArea::new(content.lines().map(|s| s.to_string()).collect());
+        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        // Завданням без ID присвоюємо його одразу і зберігаємо, щоб ID не змінювались між запусками
+        if *filename == "todo.txt" {
+            if let Some(with_ids) = ensure_task_ids(&lines) { fs::write(filename, with_ids.join("\n")).ok(); lines = with_ids; }
+        }
+        let mut ta = TextArea::new(lines);
         ta.set_max_histories(10000);
         ta.set_block(Block::default().borders(Borders::ALL));
         ta.set_search_style(Style::default().bg(Color::Yellow).fg(Color::Black));
@@ -41,11 +67,11 @@ fn main() -> Result<()> {
 
     let config_path = "config.json";
     let config_data = fs::read_to_string(config_path).unwrap_or_else(|_| r#"{ "targets": [], "commands": [] }"#.to_string());
-    let config: AppConfig = serde_json::from_str(&config_data).unwrap_or(AppConfig { targets: vec![], commands: vec![] });
+    let mut config: AppConfig = serde_json::from_str(&config_data).unwrap_or_default();
+    config.resolve_platform();
 
     // --- ЗАВАНТАЖЕННЯ ДАНИХ (Sync Text -> JSON) ---
-    let todo_content = fs::read_to_string("todo.txt").unwrap_or_default();
-    let mut tasks = parse_tasks_from_text(&todo_content);
+    let mut tasks = parse_tasks_from_text(&textareas[1].lines().join("\n"));
 
     // Резерв: якщо todo.txt пустий, пробуємо взяти з json
     if tasks.is_empty() {
@@ -61,17 +87,38 @@ fn main() -> Result<()> {
     if !config.targets.is_empty() { table_state.select(Some(0)); }
 
     let mut active_view = ActiveView::Editor(EditorMode::Notes);
+
+    // --- JOBS: окремий буфер виводу для кожного запуску ---
+    let mut jobs: Vec<Job> = Vec::new();
+    let mut jobs_list_state = ListState::default();
+    let mut job_viewer = TextArea::default();
+    let mut job_viewer_key = (0usize, usize::MAX); // (id, к-сть рядків), щоб не перебудовувати зайвий раз
+    let mut focus_job: Option<usize> = None;
+
+    let mut runbook_runs: Vec<RunbookRun> = Vec::new();
+    let mut history = load_history();
+    let mut diag_run: Option<DiagRun> = None; // Лише останній запуск діагностики
+    let mut next_diag_id = 0usize;
+    let mut reminders: Vec<Reminder> = Vec::new(); // Черга вікна нагадувань: показується перше
+    let mut snoozes = load_snoozes();
+    let mut dismissed = load_dismissed();
+    let mut task_filter = String::new(); // Фільтр панелі Schedule, лишається і без фокусу
+
+    // Позначені пробілом рядки таблиці серверів (індекси в config.targets)
+    let mut selected_targets: BTreeSet<usize> = BTreeSet::new();
+    let mut palette_usage = load_usage();
     let (tx, rx) = mpsc::channel::<AppEvent>();
     let (tx_to_monitor, rx_from_main) = mpsc::channel::<MonitorCommand>();
 
     let mut clipboard = Clipboard::new().ok();
     let mut last_user_activity = Instant::now();
-    let mut files_modified = vec![false, false, false];
+    let mut files_modified = [false, false, false];
     let mut tasks_modified = false;
 
     // --- ЗАПУСК МОНІТОРА (ЗАМІСТЬ ВЕЛИКОГО БЛОКУ thread::spawn) ---
     // Ми просто викликаємо функцію, передаючи туди копії даних
-    start_monitor(config.targets.clone(), tasks.clone(), tx.clone(), rx_from_main);
+    start_monitor(config.targets.clone(), tasks.clone(), config.commands.clone(), config.schedules.clone(), tx.clone(), rx_from_main);
+    start_discovery(config.discovery.clone(), config.diag.clone(), tx.clone());
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -84,6 +131,7 @@ fn main() -> Result<()> {
     let tick_rate = Duration::from_millis(100);
     let mut last_tick = Instant::now();
     let mut is_selecting = false;
+    let mut last_editor = EditorMode::Notes; // Звідки брати виділення для `{selection}`
 
     loop {
         // --- СИНХРОНІЗАЦІЯ (TEXT -> JSON) ---
@@ -99,55 +147,119 @@ fn main() -> Result<()> {
             match event {
                 AppEvent::ServerUpdate(data) => { server_data = data; should_redraw = true; }
                 AppEvent::LogOutput(text) => {
-                    let log_textarea = &mut textareas[2];
-                    if text.starts_with('[') { log_textarea.insert_str(text); }
-                    else { log_textarea.insert_str(format!("[{}] Output:\n{}", Local::now().format("%H:%M:%S"), text)); }
-                    log_textarea.insert_str("\n-------------------------------------------\n");
+                    let line = if text.starts_with('[') { text } else { format!("[{}] {}", Local::now().format("%H:%M:%S"), text) };
+                    append_log_line(&mut textareas[2], &line);
                     files_modified[2] = true; should_redraw = true;
                 }
-                AppEvent::TaskCompleted(title) => {
-                    // Логіка зміни тексту залишається тут, бо `textareas` живуть у main
-                    let todo_textarea = &mut textareas[1];
-                    let old_lines = todo_textarea.lines().to_vec();
-                    let mut new_lines = Vec::new();
-                    let mut modified = false;
-
-                    for line in old_lines {
-                        if line.contains(&title) && line.trim().starts_with("- [") && !line.contains("[x]") && !line.contains("[X]") {
-                            let start_bracket = line.find('[').unwrap_or(0);
-                            let end_bracket = line.find(']').unwrap_or(line.len());
-                            if end_bracket > start_bracket {
-                                let new_line = format!("{}[x]{}", &line[..start_bracket], &line[end_bracket + 1..]);
-                                new_lines.push(new_line);
-                                modified = true;
-                            } else { new_lines.push(line); }
-                        } else { new_lines.push(line); }
+                AppEvent::JobStarted { id, spec } => {
+                    jobs.push(Job { id, name: spec.name.clone(), command_line: spec.command_line(), output: Vec::new(), status: JobStatus::Running, started: Local::now(), duration: None, target: spec.target.clone(), batch: spec.batch, spec: *spec });
+                    if focus_job == Some(id) || jobs_list_state.selected().is_none() { jobs_list_state.select(Some(jobs.len() - 1)); focus_job = None; }
+                    should_redraw = true;
+                }
+                AppEvent::JobOutput(id, line) => {
+                    if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+                        if job.output.len() < 20_000 { job.output.push(line); }
+                        should_redraw = true;
                     }
-
-                    if modified {
-                        *todo_textarea = TextArea::new(new_lines);
-                        todo_textarea.set_block(Block::default().borders(Borders::ALL));
-
-                        // Примусова синхронізація
-                        let content = todo_textarea.lines().join("\n");
-                        tasks = parse_tasks_from_text(&content);
-                        let _ = tx_to_monitor.send(MonitorCommand::UpdateTasks(tasks.clone()));
-
-                        files_modified[1] = true;
-                        tasks_modified = true;
+                }
+                AppEvent::JobFinished { id, status, duration } => {
+                    if let Some(job) = jobs.iter_mut().find(|j| j.id == id) {
+                        job.status = status;
+                        job.duration = Some(duration);
+                        append_log_line(&mut textareas[2], &format_job_summary(job));
+                        record(&mut history, HistoryEntry::from_job(job));
+                        files_modified[2] = true; should_redraw = true;
+                    }
+                }
+                AppEvent::TaskRunFinished { task_id, title, job, status } => {
+                    // Підсумок — в опис завдання; закриваємо лише при exit 0, інакше — у вікно нагадувань
+                    let now = Local::now();
+                    let ok = status == JobStatus::Exited(0);
+                    let last_line = jobs.iter().find(|j| j.id == job).and_then(|j| j.output.iter().rev().find(|l| !l.trim().is_empty())).map(|l| l.trim().chars().take(80).collect::<String>());
+                    let outcome = if ok { "✓ exit 0".to_string() } else { format!("✗ {}", failure_reason(&status)) };
+                    let result = format!("{} job #{} {}{}", now.format("%Y-%m-%d %H:%M"), job, outcome, last_line.map(|l| format!(": {}", l)).unwrap_or_default());
+                    let mut lines = textareas[1].lines().to_vec();
+                    if let Some(attached) = attach_run_result(&lines, &task_id, &title, &result) { lines = attached; }
+                    if ok {
+                        if let Some(done) = complete_task(&lines, &task_id, &title, now.naive_local()) { lines = done; }
+                    } else {
+                        let reminder = Reminder { task_id, title: title.clone(), description: format!("Запуск не вдався ({}), вивід — job #{} у вкладці Jobs", failure_reason(&status), job), due: now.format("%Y-%m-%d %H:%M").to_string(), missed: false };
+                        if !reminders.iter().any(|r| r.same_task(&reminder)) { reminders.push(reminder); }
+                    }
+                    replace_lines(&mut textareas[1], &lines);
+                    append_log_line(&mut textareas[2], &format!("[{}] 📅 Task '{}': {}", now.format("%H:%M:%S"), title, outcome));
+                    files_modified[1] = true; files_modified[2] = true; should_redraw = true;
+                }
+                AppEvent::RunbookStep { run, step, state, job, note } => {
+                    if let Some(r) = runbook_runs.iter_mut().find(|r| r.id == run) {
+                        if step < r.steps.len() {
+                            r.steps[step] = state;
+                            if job.is_some() { r.step_jobs[step] = job; }
+                            if let Some(n) = note { r.notes[step] = n; }
+                        }
+                    }
+                    should_redraw = true;
+                }
+                AppEvent::RunbookFinished { run, ok } => {
+                    if let Some(r) = runbook_runs.iter_mut().find(|r| r.id == run) {
+                        r.finished = Some(ok);
+                        for s in r.steps.iter_mut() { if *s == StepState::Pending { *s = StepState::Skipped; } }
+                    }
+                    should_redraw = true;
+                }
+                AppEvent::DiagRow { run, key, cells } => {
+                    if let Some(r) = diag_run.as_mut().filter(|r| r.id == run) {
+                        match r.rows.binary_search_by_key(&key, |(k, _)| *k) {
+                            Ok(i) => r.rows[i].1 = cells,
+                            Err(i) => r.rows.insert(i, (key, cells)),
+                        }
+                        should_redraw = true;
+                    }
+                }
+                AppEvent::DiagDone { run, summary } => {
+                    if let Some(r) = diag_run.as_mut().filter(|r| r.id == run) {
+                        append_log_line(&mut textareas[2], &format!("[{}] 🔎 {} {}: {}", Local::now().format("%H:%M:%S"), tool_label(r.tool), r.input, summary));
+                        files_modified[2] = true;
+                        r.summary = Some(summary);
                         should_redraw = true;
                     }
                 }
+                AppEvent::ReminderDue(reminder) => {
+                    // Відкладене чекає свого часу; те саме завдання двічі у черзі не тримаємо
+                    if !is_snoozed(&snoozes, &reminder) && !reminders.iter().any(|r| r.same_task(&reminder)) { reminders.push(reminder); }
+                    should_redraw = true;
+                }
             }
         }
 
+        // --- ВІДКЛАДЕНІ НАГАДУВАННЯ (snoozes.json) ---
+        for reminder in take_due(&mut snoozes, Local::now().naive_local()) {
+            if !reminders.iter().any(|r| r.same_task(&reminder)) { reminders.push(reminder); }
+            should_redraw = true;
+        }
+
         if last_tick.elapsed() >= tick_rate { should_redraw = true; last_tick = Instant::now(); }
 
+        // --- ПЕРЕГЛЯДАЧ ВИВОДУ ОБРАНОГО JOB ---
+        if let Some(job) = jobs_list_state.selected().and_then(|i| jobs.get(i)) {
+            if job_viewer_key != (job.id, job.output.len()) {
+                let (row, col) = job_viewer.cursor();
+                let follow = job_viewer_key.0 != job.id || row + 1 >= job_viewer.lines().len();
+                let pattern = job_viewer.search_pattern().map(|r| r.as_str().to_string());
+                job_viewer = TextArea::new(job.output.clone());
+                job_viewer.set_block(Block::default().borders(Borders::ALL).title(format!(" #{} {} ", job.id, job.name)).title_bottom(format!(" $ {} ", job.command_line)));
+                job_viewer.set_search_style(Style::default().bg(Color::Yellow).fg(Color::Black));
+                if let Some(p) = pattern { job_viewer.set_search_pattern(p).ok(); }
+                if follow { job_viewer.move_cursor(CursorMove::Bottom); } else { job_viewer.move_cursor(CursorMove::Jump(row as u16, col as u16)); }
+                job_viewer_key = (job.id, job.output.len());
+            }
+        }
+
         // --- МАЛЮВАННЯ (ЗАМІСТЬ ВЕЛИКОГО БЛОКУ terminal.draw) ---
         if should_redraw {
             terminal.draw(|f| {
                 // Викликаємо функцію з ui.rs
-                draw(f, &textareas, &server_data, &tasks, &active_view, &mut table_state, &mut list_state, &config.commands, &titles);
+                draw(f, &textareas, &server_data, &tasks, &active_view, &mut table_state, &mut list_state, &config, &titles, &jobs, &mut jobs_list_state, &job_viewer, &selected_targets, &runbook_runs, &history, diag_run.as_ref(), &reminders, &dismissed, &task_filter);
             })?;
             should_redraw = false;
         }
@@ -164,17 +276,91 @@ fn main() -> Result<()> {
                         files_modified[mode as usize] = true; should_redraw = true;
                     }
                 }
+                // --- ВІКНО НАГАДУВАННЯ: перехоплює клавіші, доки черга не порожня ---
+                Event::Key(key) if key.kind == KeyEventKind::Press && !reminders.is_empty() => {
+                    last_user_activity = Instant::now();
+                    should_redraw = true;
+                    if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('q') || key.code == KeyCode::Char('й')) { break; }
+                    let timestamp = Local::now().format("%H:%M:%S");
+                    let note = match key.code {
+                        KeyCode::Enter | KeyCode::Char('d') | KeyCode::Char('в') => {
+                            let reminder = reminders.remove(0);
+                            match complete_task(textareas[1].lines(), &reminder.task_id, &reminder.title, Local::now().naive_local()) {
+                                Some(lines) => {
+                                    replace_lines(&mut textareas[1], &lines);
+                                    files_modified[1] = true;
+                                    Some(format!("[{}] ✅ Done: {}", timestamp, reminder.title))
+                                }
+                                None => Some(format!("[{}] ✅ Done: {} (відкритого рядка вже немає)", timestamp, reminder.title)),
+                            }
+                        }
+                        KeyCode::Char(c @ '1'..='3') => {
+                            let minutes = SNOOZE_MINUTES[c as usize - '1' as usize];
+                            let reminder = reminders.remove(0);
+                            let title = reminder.title.clone();
+                            let until = snooze(&mut snoozes, reminder, minutes);
+                            Some(format!("[{}] 💤 Snoozed '{}' until {}", timestamp, title, until.format("%H:%M")))
+                        }
+                        KeyCode::Esc => {
+                            // Повторюване переходить на наступне спрацювання, для одноразового запам'ятовуємо відхилений час
+                            let mut reminder = reminders.remove(0);
+                            let title = reminder.title.clone();
+                            let now = Local::now().naive_local();
+                            match tasks.iter().find(|t| reminder.is_for(t)) {
+                                Some(task) if task.recurrence.is_some() => {
+                                    if let Some(lines) = complete_task(textareas[1].lines(), &reminder.task_id, &reminder.title, now) {
+                                        replace_lines(&mut textareas[1], &lines);
+                                        files_modified[1] = true;
+                                    }
+                                }
+                                Some(task) => {
+                                    if let Some(due) = due_at(task, now) { reminder.due = due.format(DUE_FORMAT).to_string(); }
+                                    dismiss(&mut dismissed, reminder, &tasks, now);
+                                }
+                                None => {}
+                            }
+                            Some(format!("[{}] 🔕 Dismissed: {}", timestamp, title))
+                        }
+                        _ => None,
+                    };
+                    if let Some(line) = note { append_log_line(&mut textareas[2], &line); files_modified[2] = true; }
+                }
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
                     last_user_activity = Instant::now();
                     should_redraw = true;
                     let mut change_view = None;
+                    let mut launch: Option<(PendingLaunch, bool)> = None; // (виклик, вже підтверджено)
+                    let mut open_command: Option<usize> = None; // Enter в Actions або вибір у палітрі
+                    let mut pick: Option<PaletteAction> = None;
+                    let mut run_runbook: Option<usize> = None; // Індекс runbook, який стартуємо
 
                     if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('q') || key.code == KeyCode::Char('й')) { break; }
                     if key.modifiers == KeyModifiers::ALT && (key.code == KeyCode::Char('t') || key.code == KeyCode::Char('е')) {
-                        change_view = Some(ActiveView::TodoWizard { step: WizardStep::Title, buffer: String::new(), temp_title: String::new(), temp_desc: String::new() });
+                        change_view = Some(ActiveView::TodoWizard { step: WizardStep::Title, buffer: String::new(), temp_title: String::new(), temp_desc: String::new(), temp_time: String::new(), edit: None });
+                    }
+                    let palette_key = key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('p') || key.code == KeyCode::Char('з'));
+                    if palette_key {
+                        change_view = Some(ActiveView::Palette { query: String::new(), selected: 0, matches: rank(build_items(&config, &tasks), "", &palette_usage), back: last_editor });
                     }
 
                     match &mut active_view {
+                        _ if palette_key => {}
+                        ActiveView::Palette { query, selected, matches, back } => {
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Editor(*back)); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), matches.len()).unwrap_or(0); }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), matches.len()).unwrap_or(0); }
+                                KeyCode::Enter => {
+                                    if let Some(item) = matches.get(*selected) {
+                                        record_usage(&mut palette_usage, &item.key);
+                                        pick = Some(item.action.clone());
+                                    }
+                                }
+                                KeyCode::Backspace => { query.pop(); *matches = rank(build_items(&config, &tasks), query, &palette_usage); *selected = 0; }
+                                KeyCode::Char(c) => { query.push(c); *matches = rank(build_items(&config, &tasks), query, &palette_usage); *selected = 0; }
+                                _ => {}
+                            }
+                        }
                         ActiveView::Search { mode_return_to, query } => {
                             let idx = *mode_return_to as usize;
                             match key.code {
@@ -185,58 +371,71 @@ fn main() -> Result<()> {
                                 _ => {}
                             }
                         }
-                        ActiveView::InputPopup { command_idx, input_buffer } => {
+                        ActiveView::InputPopup { command_idx, fields, focused, targets } => {
                             match key.code {
                                 KeyCode::Enter => {
-                                    let idx_copy = *command_idx;
-                                    if idx_copy < config.commands.len() {
-                                        let cmd_struct = config.commands[idx_copy].clone();
-                                        let buffer_clone = input_buffer.clone();
-                                        let final_args: Vec<String> = cmd_struct.args.iter().map(|arg| if arg == "%INPUT%" { buffer_clone.clone() } else { arg.clone() }).collect();
-                                        change_view = Some(ActiveView::Editor(EditorMode::Logs));
-                                        let tx_cmd = tx.clone();
-                                        let cmd_exe = cmd_struct.cmd.clone();
-                                        thread::spawn(move || {
-                                            let output = Command::new(cmd_exe).args(final_args).output();
-                                            let mut result_text = String::new();
-                                            match output {
-                                                Ok(o) => {
-                                                    let (decoded_str, _, _) = IBM866.decode(&o.stdout);
-                                                    result_text.push_str(&decoded_str);
-                                                    if !o.stderr.is_empty() { let (err_str, _, _) = IBM866.decode(&o.stderr); result_text.push_str("\nERROR:\n"); result_text.push_str(&err_str); }
-                                                },
-                                                Err(e) => { result_text.push_str(&format!("Failed to run: {}", e)); }
-                                            }
-                                            let text = result_text.trim();
-                                            if !text.is_empty() { let _ = tx_cmd.send(AppEvent::LogOutput(text.to_string())); }
-                                        });
+                                    // Спершу валідуємо всі поля, помилки показуємо під кожним
+                                    for field in fields.iter_mut() { field.error = validate_param(&field.param, &field.value, &config.targets).err(); }
+                                    if let Some(first_bad) = fields.iter().position(|f| f.error.is_some()) { *focused = first_bad; }
+                                    else {
+                                        launch = Some((PendingLaunch { command_idx: *command_idx, values: form_values(fields, &config.targets), targets: targets.clone() }, false));
                                     }
                                 }
-                                KeyCode::Esc => { change_view = Some(ActiveView::Actions); }
-                                KeyCode::Backspace => { input_buffer.pop(); }
-                                KeyCode::Char(c) => { input_buffer.push(c); }
+                                KeyCode::Esc => { change_view = Some(if targets.is_empty() { ActiveView::Actions } else { ActiveView::Servers }); }
+                                KeyCode::Tab if !fields.is_empty() => { *focused = (*focused + 1) % fields.len(); }
+                                KeyCode::BackTab if !fields.is_empty() => { *focused = (*focused + fields.len() - 1) % fields.len(); }
+                                KeyCode::Left | KeyCode::Right => { if let Some(field) = fields.get_mut(*focused) { cycle_choice(field, &config.targets, key.code == KeyCode::Right); } }
+                                KeyCode::Up | KeyCode::Down => {
+                                    // Попередні значення цього поля з history.json: Up — старіше, Down — новіше
+                                    if let (Some(field), Some(cmd_struct)) = (fields.get_mut(*focused), config.commands.get(*command_idx)) {
+                                        let previous = recent_values(&history, &cmd_struct.name, &field.param.name);
+                                        let next = match (key.code, previous.iter().position(|v| *v == field.value)) {
+                                            (KeyCode::Up, None) => Some(0),
+                                            (KeyCode::Up, Some(p)) => Some((p + 1).min(previous.len() - 1)),
+                                            (KeyCode::Down, Some(p)) if p > 0 => Some(p - 1),
+                                            _ => None,
+                                        };
+                                        if let Some(value) = next.and_then(|n| previous.get(n)) { field.value = value.clone(); field.error = None; }
+                                    }
+                                }
+                                KeyCode::Backspace => { if let Some(field) = fields.get_mut(*focused) { field.value.pop(); field.error = None; } }
+                                KeyCode::Char(c) => { if let Some(field) = fields.get_mut(*focused) { field.value.push(c); field.error = None; } }
                                 _ => {}
                             }
                         }
-                        ActiveView::TodoWizard { step, buffer, temp_title, temp_desc } => {
+                        ActiveView::TodoWizard { step, buffer, temp_title, temp_desc, temp_time, edit } => {
+                            let back = if edit.is_some() { ActiveView::Schedule { selected: 0, filtering: false } } else { ActiveView::Editor(EditorMode::Todo) };
                             match key.code {
-                                KeyCode::Esc => { change_view = Some(ActiveView::Editor(EditorMode::Todo)); }
+                                KeyCode::Esc => { change_view = Some(back); }
                                 KeyCode::Backspace => { buffer.pop(); }
                                 KeyCode::Char(c) => { buffer.push(c); }
                                 KeyCode::Enter => {
                                     match step {
-                                        WizardStep::Title => { if !buffer.is_empty() { *temp_title = buffer.clone(); buffer.clear(); *step = WizardStep::Description; } }
-                                        WizardStep::Description => { *temp_desc = buffer.clone(); buffer.clear(); *step = WizardStep::Time; }
+                                        // Наступний крок починається з наявного значення (при редагуванні — поточного)
+                                        WizardStep::Title => { if !buffer.is_empty() { *temp_title = buffer.clone(); *buffer = temp_desc.clone(); *step = WizardStep::Description; } }
+                                        WizardStep::Description => { *temp_desc = buffer.clone(); *buffer = temp_time.clone(); *step = WizardStep::Time; }
                                         WizardStep::Time => {
-                                            if is_valid_time(&buffer) {
-                                                let time_str = buffer.trim().to_string();
-                                                let display_str = if time_str.is_empty() { format!("- [ ] {}\n      {}", temp_title, temp_desc) } else { format!("- [{}] {}\n      {}", time_str, temp_title, temp_desc) };
+                                            if let Some(time_str) = resolve_when(buffer, Local::now().naive_local()) {
+                                                let id = edit.as_ref().map(|(id, _)| id.clone()).filter(|id| !id.is_empty()).unwrap_or_else(|| new_task_id(textareas[1].lines()));
+                                                let bracket = if time_str.is_empty() { " ".to_string() } else { time_str };
                                                 let todo_area = &mut textareas[1];
-                                                todo_area.move_cursor(CursorMove::Bottom);
-                                                if !todo_area.lines().is_empty() { todo_area.insert_str("\n"); }
-                                                todo_area.insert_str(display_str);
+                                                match edit.as_ref().and_then(|(id, title)| task_block(todo_area.lines(), id, title)) {
+                                                    // Редагування: замінюємо рядок завдання з описом на місці, ID той самий
+                                                    Some(block) => {
+                                                        let mut lines = todo_area.lines().to_vec();
+                                                        let mut new_block = vec![format!("- [{}] {} ^{}", bracket, temp_title, id)];
+                                                        if !temp_desc.is_empty() { new_block.extend(temp_desc.lines().map(|l| format!("      {}", l))); }
+                                                        lines.splice(block, new_block);
+                                                        replace_lines(todo_area, &lines);
+                                                    }
+                                                    None => {
+                                                        todo_area.move_cursor(CursorMove::Bottom);
+                                                        if !todo_area.lines().is_empty() { todo_area.insert_str("\n"); }
+                                                        todo_area.insert_str(format!("- [{}] {} ^{}\n      {}", bracket, temp_title, id, temp_desc));
+                                                    }
+                                                }
                                                 files_modified[1] = true;
-                                                change_view = Some(ActiveView::Editor(EditorMode::Todo));
+                                                change_view = Some(back);
                                             }
                                         }
                                     }
@@ -245,6 +444,7 @@ fn main() -> Result<()> {
                             }
                         }
                         ActiveView::Editor(mode) => {
+                            last_editor = *mode;
                             let idx = *mode as usize;
                             let textarea = &mut textareas[idx];
                             // ... Тут довгий блок обробки клавіш (Ctrl+C, Ctrl+V, і т.д.) ...
@@ -268,50 +468,496 @@ fn main() -> Result<()> {
                                     KeyCode::Char('1') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
                                     KeyCode::Char('2') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Todo)); }
                                     KeyCode::Char('3') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Logs)); }
+                                    KeyCode::Char('4') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Jobs); }
+                                    KeyCode::Char('5') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Runbooks { selected: 0, step: 0 }); }
+                                    KeyCode::Char('6') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::History { filter: String::new(), selected: 0 }); }
+                                    KeyCode::Char('7') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(diag_view(diag_run.as_ref())); }
                                     KeyCode::Char(_) | KeyCode::Enter | KeyCode::Backspace | KeyCode::Delete => { if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT { is_selecting = false; } textarea.input(key); files_modified[idx] = true; },
                                     KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => { if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT { is_selecting = false; } textarea.input(key); }
                                     _ => {}
                                 }
                             }
                         }
-                        ActiveView::Actions => {
+                        ActiveView::Jobs => {
+                            let selected_job = jobs_list_state.selected().and_then(|i| jobs.get(i));
+                            if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('f') || key.code == KeyCode::Char('а')) { change_view = Some(ActiveView::JobSearch { query: String::new() }); }
+                            else if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('c') || key.code == KeyCode::Char('с')) {
+                                if let (Some(job), Some(cb)) = (selected_job, &mut clipboard) { let _ = cb.set_text(job.output.join("\n")); }
+                            }
+                            else if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('s') || key.code == KeyCode::Char('і')) {
+                                if let Some(job) = selected_job { change_view = Some(ActiveView::JobSaveAs { buffer: format!("job_{}.log", job.id) }); }
+                            }
+                            else {
+                                match key.code {
+                                    KeyCode::Esc | KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                    KeyCode::Char('1') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                    KeyCode::Char('2') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Todo)); }
+                                    KeyCode::Char('3') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Editor(EditorMode::Logs)); }
+                                    KeyCode::Char('5') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Runbooks { selected: 0, step: 0 }); }
+                                    KeyCode::Char('6') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::History { filter: String::new(), selected: 0 }); }
+                                    KeyCode::Char('7') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(diag_view(diag_run.as_ref())); }
+                                    KeyCode::Down => { select_next(&mut jobs_list_state, jobs.len()); }
+                                    KeyCode::Up => { select_prev(&mut jobs_list_state, jobs.len()); }
+                                    KeyCode::PageDown => { job_viewer.scroll(Scrolling::PageDown); }
+                                    KeyCode::PageUp => { job_viewer.scroll(Scrolling::PageUp); }
+                                    KeyCode::Home => { job_viewer.move_cursor(CursorMove::Top); }
+                                    KeyCode::End => { job_viewer.move_cursor(CursorMove::Bottom); }
+                                    KeyCode::Delete => {
+                                        // Прибираємо лише завершені, щоб не загубити вивід живого процесу
+                                        if let Some(i) = jobs_list_state.selected() {
+                                            if i < jobs.len() && jobs[i].status != JobStatus::Running {
+                                                jobs.remove(i);
+                                                jobs_list_state.select(if jobs.is_empty() { None } else { Some(i.min(jobs.len() - 1)) });
+                                                job_viewer = TextArea::default();
+                                                job_viewer_key = (0, usize::MAX);
+                                            }
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        ActiveView::JobSearch { query } => {
                             match key.code {
-                                KeyCode::Esc => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
-                                KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
-                                KeyCode::Down => { if !config.commands.is_empty() { let i = match list_state.selected() { Some(i) => if i >= config.commands.len() - 1 { 0 } else { i + 1 }, None => 0, }; list_state.select(Some(i)); } }
-                                KeyCode::Up => { if !config.commands.is_empty() { let i = match list_state.selected() { Some(i) => if i == 0 { config.commands.len() - 1 } else { i - 1 }, None => 0, }; list_state.select(Some(i)); } }
+                                KeyCode::Esc => { job_viewer.set_search_pattern("").ok(); change_view = Some(ActiveView::Jobs); }
+                                KeyCode::Enter => { job_viewer.search_forward(false); }
+                                KeyCode::Backspace => { query.pop(); job_viewer.set_search_pattern(query.as_str()).ok(); }
+                                KeyCode::Char(c) => { query.push(c); job_viewer.set_search_pattern(query.as_str()).ok(); job_viewer.search_forward(false); }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::JobSaveAs { buffer } => {
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Jobs); }
+                                KeyCode::Backspace => { buffer.pop(); }
+                                KeyCode::Char(c) => { buffer.push(c); }
                                 KeyCode::Enter => {
-                                    if let Some(i) = list_state.selected() {
-                                        if i < config.commands.len() {
-                                            let cmd_struct = config.commands[i].clone();
-                                            if cmd_struct.args.contains(&"%INPUT%".to_string()) {
-                                                change_view = Some(ActiveView::InputPopup { command_idx: i, input_buffer: String::new() });
-                                            } else {
-                                                change_view = Some(ActiveView::Editor(EditorMode::Logs));
-                                                let tx_cmd = tx.clone();
-                                                let cmd_exe = cmd_struct.cmd.clone();
-                                                let cmd_args = cmd_struct.args.clone();
-                                                thread::spawn(move || {
-                                                    let output = Command::new(cmd_exe).args(cmd_args).output();
-                                                    let mut result_text = String::new();
-                                                    match output {
-                                                        Ok(o) => {
-                                                            let (decoded_str, _, _) = IBM866.decode(&o.stdout);
-                                                            result_text.push_str(&decoded_str);
-                                                            if !o.stderr.is_empty() { let (err_str, _, _) = IBM866.decode(&o.stderr); result_text.push_str("\nERROR:\n"); result_text.push_str(&err_str); }
-                                                        },
-                                                        Err(e) => { result_text.push_str(&format!("Failed to run: {}", e)); }
+                                    if let Some(job) = jobs_list_state.selected().and_then(|i| jobs.get(i)) {
+                                        let path = buffer.trim();
+                                        let msg = match fs::write(path, job.output.join("\n")) {
+                                            Ok(_) => format!("[{}] 💾 Job #{} saved to {}", Local::now().format("%H:%M:%S"), job.id, path),
+                                            Err(e) => format!("[{}] ❌ Job #{} not saved to {}: {}", Local::now().format("%H:%M:%S"), job.id, path, e),
+                                        };
+                                        append_log_line(&mut textareas[2], &msg);
+                                        files_modified[2] = true;
+                                    }
+                                    change_view = Some(ActiveView::Jobs);
+                                }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::History { filter, selected } => {
+                            let visible = filter_history(&history, filter);
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                KeyCode::Char('4') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Jobs); }
+                                KeyCode::Char('5') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Runbooks { selected: 0, step: 0 }); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), visible.len()).unwrap_or(0); }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), visible.len()).unwrap_or(0); }
+                                KeyCode::Enter => {
+                                    // Повтор: та сама команда з тими ж значеннями (і підтвердженням, якщо воно потрібне)
+                                    if let Some(entry) = visible.get(*selected).map(|i| &history[*i]) {
+                                        match config.commands.iter().position(|c| c.name == entry.command) {
+                                            Some(command_idx) => {
+                                                let targets = entry.target.as_ref().and_then(|name| config.targets.iter().position(|t| t.name == *name)).into_iter().collect();
+                                                launch = Some((PendingLaunch { command_idx, values: entry.values.clone(), targets }, false));
+                                            }
+                                            None => {
+                                                append_log_line(&mut textareas[2], &format!("[{}] ⟲ Re-run: немає команди '{}' у config.json", Local::now().format("%H:%M:%S"), entry.command));
+                                                files_modified[2] = true;
+                                            }
+                                        }
+                                    }
+                                }
+                                KeyCode::Backspace => { filter.pop(); *selected = 0; }
+                                KeyCode::Char(c) => { filter.push(c); *selected = 0; }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::Diagnostics { tool, input, selected, marked } => {
+                            let rows_len = diag_run.as_ref().map(|r| r.rows.len()).unwrap_or(0);
+                            let running = diag_run.as_ref().filter(|r| r.summary.is_none());
+                            let discovery = diag_run.as_ref().filter(|r| r.tool == DiagTool::Discover);
+                            let current_key = diag_run.as_ref().and_then(|r| r.rows.get(*selected)).map(|(k, _)| *k);
+                            if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('c') || key.code == KeyCode::Char('с')) {
+                                if let (Some(r), Some(cb)) = (diag_run.as_ref(), &mut clipboard) { let _ = cb.set_text(rows_as_text(r)); }
+                            }
+                            else if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('a') || key.code == KeyCode::Char('ф')) {
+                                // Позначити всі нові хости (або зняти позначки)
+                                if let Some(r) = discovery {
+                                    if marked.is_empty() { *marked = r.rows.iter().filter(|(_, c)| c.get(3).is_some_and(|s| s == NEW_HOST)).map(|(k, _)| *k).collect(); } else { marked.clear(); }
+                                }
+                            }
+                            else if key.modifiers == KeyModifiers::CONTROL && (key.code == KeyCode::Char('s') || key.code == KeyCode::Char('і')) {
+                                // Імпорт позначених (або поточного) нових хостів у config.json
+                                if let Some(r) = discovery {
+                                    let keys: Vec<usize> = if marked.is_empty() { current_key.into_iter().collect() } else { marked.iter().copied().collect() };
+                                    let new_targets = discovered_targets(r, &keys, &config.targets);
+                                    let timestamp = Local::now().format("%H:%M:%S");
+                                    let msg = if new_targets.is_empty() { format!("[{}] 🔎 Discovery: немає нових хостів серед обраних", timestamp) } else {
+                                        match append_targets(config_path, &new_targets) {
+                                            Ok(()) => {
+                                                let names: Vec<String> = new_targets.iter().map(|t| format!("{} ({})", t.name, t.address)).collect();
+                                                if let Some(r) = diag_run.as_mut() {
+                                                    for t in &new_targets {
+                                                        let ip = split_address(&t.address).0;
+                                                        if let Some((_, cells)) = r.rows.iter_mut().find(|(_, c)| c.first() == Some(&ip)) { cells[3] = format!("✓ {}", t.name); }
                                                     }
-                                                    let text = result_text.trim();
-                                                    if !text.is_empty() { let _ = tx_cmd.send(AppEvent::LogOutput(text.to_string())); }
-                                                });
+                                                }
+                                                config.targets.extend(new_targets);
+                                                let _ = tx_to_monitor.send(MonitorCommand::UpdateTargets(config.targets.clone()));
+                                                marked.clear();
+                                                format!("[{}] 🔎 Discovery: додано в {} — {}", timestamp, config_path, names.join(", "))
                                             }
+                                            Err(e) => format!("[{}] ❌ Discovery: {}", timestamp, e),
                                         }
+                                    };
+                                    append_log_line(&mut textareas[2], &msg);
+                                    files_modified[2] = true;
+                                }
+                            }
+                            else {
+                                match key.code {
+                                    // Перший Esc зупиняє запуск, другий — виходить
+                                    KeyCode::Esc => match running {
+                                        Some(r) => r.cancel.store(true, Ordering::Relaxed),
+                                        None => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                    },
+                                    KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                    KeyCode::Char('4') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Jobs); }
+                                    KeyCode::Char('5') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Runbooks { selected: 0, step: 0 }); }
+                                    KeyCode::Char('6') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::History { filter: String::new(), selected: 0 }); }
+                                    KeyCode::Left | KeyCode::Right => {
+                                        let current = DIAG_TOOLS.iter().position(|t| t == tool);
+                                        let next = if key.code == KeyCode::Right { wrap_next(current, DIAG_TOOLS.len()) } else { wrap_prev(current, DIAG_TOOLS.len()) };
+                                        if let Some(i) = next { *tool = DIAG_TOOLS[i]; }
+                                    }
+                                    KeyCode::Down => { *selected = wrap_next(Some(*selected), rows_len).unwrap_or(0); }
+                                    KeyCode::Up => { *selected = wrap_prev(Some(*selected), rows_len).unwrap_or(0); }
+                                    KeyCode::Insert if discovery.is_some() => {
+                                        if let Some(k) = current_key { if !marked.remove(&k) { marked.insert(k); } }
+                                        *selected = wrap_next(Some(*selected), rows_len).unwrap_or(0);
+                                    }
+                                    KeyCode::Enter if !input.trim().is_empty() => {
+                                        if let Some(r) = &diag_run { r.cancel.store(true, Ordering::Relaxed); }
+                                        next_diag_id += 1;
+                                        let cancel = start_diag(next_diag_id, *tool, resolve_input(*tool, input, &config.targets), config.diag.clone(), config.targets.clone(), tx.clone());
+                                        diag_run = Some(DiagRun { id: next_diag_id, tool: *tool, input: input.trim().to_string(), rows: Vec::new(), summary: None, cancel });
+                                        *selected = 0;
+                                        marked.clear();
+                                    }
+                                    KeyCode::Backspace => { input.pop(); }
+                                    KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => { input.push(c); }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        ActiveView::Schedule { selected, filtering } => {
+                            let now = Local::now().naive_local();
+                            let order = schedule_order(&tasks, now, true, &task_filter);
+                            *selected = (*selected).min(order.len().saturating_sub(1));
+                            let current = order.get(*selected).map(|i| &tasks[*i]);
+                            let mut edited: Option<Vec<String>> = None;
+                            match key.code {
+                                // Рядок фільтра: "#ops !!" — тег і пріоритет
+                                KeyCode::Enter if *filtering => { *filtering = false; }
+                                KeyCode::Esc if *filtering => { task_filter.clear(); *filtering = false; }
+                                KeyCode::Backspace if *filtering => { task_filter.pop(); *selected = 0; }
+                                KeyCode::Char(c) if *filtering && !key.modifiers.contains(KeyModifiers::CONTROL) => { task_filter.push(c); *selected = 0; }
+                                KeyCode::Char('/') | KeyCode::Char('.') => { *filtering = true; }
+                                KeyCode::Char('#') => {
+                                    if !task_filter.is_empty() && !task_filter.ends_with(' ') { task_filter.push(' '); }
+                                    task_filter.push('#');
+                                    *filtering = true;
+                                    *selected = 0;
+                                }
+                                KeyCode::Esc if !task_filter.is_empty() => { task_filter.clear(); }
+                                KeyCode::Esc | KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), order.len()).unwrap_or(0); }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), order.len()).unwrap_or(0); }
+                                KeyCode::Char(' ') | KeyCode::Char('x') | KeyCode::Char('ч') => {
+                                    if let Some(task) = current {
+                                        edited = if task.completed { reopen_task(textareas[1].lines(), &task.id, &task.title) } else { complete_task(textareas[1].lines(), &task.id, &task.title, now) };
+                                    }
+                                }
+                                KeyCode::Delete => {
+                                    if let Some((task, block)) = current.and_then(|t| task_block(textareas[1].lines(), &t.id, &t.title).map(|b| (t, b))) {
+                                        let mut lines = textareas[1].lines().to_vec();
+                                        lines.drain(block);
+                                        append_log_line(&mut textareas[2], &format!("[{}] 🗑 Task deleted: {}", Local::now().format("%H:%M:%S"), task.title));
+                                        files_modified[2] = true;
+                                        edited = Some(lines);
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    if let Some(block) = current.and_then(|t| task_block(textareas[1].lines(), &t.id, &t.title)) {
+                                        textareas[1].move_cursor(CursorMove::Jump(block.start as u16, 0));
+                                        change_view = Some(ActiveView::Editor(EditorMode::Todo));
+                                    }
+                                }
+                                // Редагування — той самий візард, з поточними значеннями; [T] — одразу на крок часу
+                                KeyCode::Char(c @ ('e' | 'у' | 't' | 'е')) => {
+                                    if let Some((task, block)) = current.and_then(|t| task_block(textareas[1].lines(), &t.id, &t.title).map(|b| (t, b))) {
+                                        let temp_time = schedule_text(&textareas[1].lines()[block.start]);
+                                        let time_only = c == 't' || c == 'е';
+                                        change_view = Some(ActiveView::TodoWizard {
+                                            step: if time_only { WizardStep::Time } else { WizardStep::Title },
+                                            buffer: if time_only { temp_time.clone() } else { title_with_markers(task) },
+                                            temp_title: title_with_markers(task), temp_desc: task.description.clone(), temp_time,
+                                            edit: Some((task.id.clone(), task.title.clone())),
+                                        });
+                                    }
+                                }
+                                _ => {}
+                            }
+                            if let Some(lines) = edited { replace_lines(&mut textareas[1], &lines); files_modified[1] = true; }
+                        }
+                        ActiveView::Servers => {
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                KeyCode::Tab => { change_view = Some(ActiveView::Schedule { selected: 0, filtering: false }); }
+                                KeyCode::Down => { table_state.select(wrap_next(table_state.selected(), config.targets.len())); }
+                                KeyCode::Up => { table_state.select(wrap_prev(table_state.selected(), config.targets.len())); }
+                                KeyCode::Char(' ') => {
+                                    if let Some(i) = table_state.selected() {
+                                        if !selected_targets.remove(&i) { selected_targets.insert(i); }
+                                        table_state.select(wrap_next(Some(i), config.targets.len()));
+                                    }
+                                }
+                                KeyCode::Char('a') | KeyCode::Char('ф') => {
+                                    if selected_targets.len() == config.targets.len() { selected_targets.clear(); } else { selected_targets = (0..config.targets.len()).collect(); }
+                                }
+                                KeyCode::Enter | KeyCode::Char('m') | KeyCode::Char('ь') => {
+                                    // Контекстне меню: позначені рядки, або поточний, якщо нічого не позначено
+                                    let targets: Vec<usize> = if selected_targets.is_empty() { table_state.selected().into_iter().collect() } else { selected_targets.iter().copied().collect() };
+                                    if !targets.is_empty() { change_view = Some(ActiveView::TargetMenu { targets, commands: target_commands(&config.commands), selected: 0 }); }
+                                }
+                                KeyCode::Char('c') | KeyCode::Char('с') | KeyCode::Char('r') | KeyCode::Char('к') | KeyCode::Char('s') | KeyCode::Char('і') | KeyCode::Char('b') | KeyCode::Char('и') => {
+                                    // Сесія до поточного сервера: C — за типом цілі, R/S/B — RDP/SSH/браузер
+                                    if let Some(target) = table_state.selected().and_then(|i| config.targets.get(i)) {
+                                        let kind = match key.code {
+                                            KeyCode::Char('r') | KeyCode::Char('к') => ConnectKind::Rdp,
+                                            KeyCode::Char('s') | KeyCode::Char('і') => ConnectKind::Ssh,
+                                            KeyCode::Char('b') | KeyCode::Char('и') => ConnectKind::Web,
+                                            _ => default_kind(target),
+                                        };
+                                        append_log_line(&mut textareas[2], &open_session(kind, target, &config.connect));
+                                        files_modified[2] = true;
                                     }
                                 }
+                                KeyCode::Char('w') | KeyCode::Char('ц') => {
+                                    if let Some(i) = table_state.selected() {
+                                        let online = server_data.get(i).is_some_and(|s| s.is_online);
+                                        if let Some(target) = config.targets.get(i) {
+                                            append_log_line(&mut textareas[2], &wake_target(target, online, &config.wol, &tx_to_monitor));
+                                            files_modified[2] = true;
+                                        }
+                                    }
+                                }
+                                KeyCode::Char('d') | KeyCode::Char('в') => {
+                                    // Діагностика з адресою поточного сервера
+                                    if let Some(target) = table_state.selected().and_then(|i| config.targets.get(i)) {
+                                        let tool = diag_run.as_ref().map(|r| r.tool).unwrap_or(DiagTool::PortScan);
+                                        change_view = Some(ActiveView::Diagnostics { tool, input: target.name.clone(), selected: 0, marked: BTreeSet::new() });
+                                    }
+                                }
+                                KeyCode::Char('g') | KeyCode::Char('п') => {
+                                    // Позначити наступну групу цілком; далі Enter — запуск на всіх її серверах
+                                    let groups = target_groups(&config.targets);
+                                    let current = groups.iter().position(|g| group_members(&config.targets, g).into_iter().collect::<BTreeSet<_>>() == selected_targets);
+                                    if let Some(group) = wrap_next(current, groups.len()).and_then(|i| groups.get(i)) { selected_targets = group_members(&config.targets, group).into_iter().collect(); }
+                                }
                                 _ => {}
                             }
                         }
+                        ActiveView::TargetMenu { targets, commands, selected } => {
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Servers); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), commands.len()).unwrap_or(0); }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), commands.len()).unwrap_or(0); }
+                                KeyCode::Enter => {
+                                    if let Some(&cmd_idx) = commands.get(*selected) {
+                                        let cmd_struct = &config.commands[cmd_idx];
+                                        let params: Vec<CommandParam> = cmd_struct.form_params().into_iter().filter(|p| !(p.name == "target" && p.kind == ParamKind::Target)).collect();
+                                        if !params.is_empty() {
+                                            change_view = Some(ActiveView::InputPopup { command_idx: cmd_idx, fields: build_form(params, &config.targets), focused: 0, targets: targets.clone() });
+                                        } else {
+                                            launch = Some((PendingLaunch { command_idx: cmd_idx, values: HashMap::new(), targets: targets.clone() }, false));
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::BatchResults { batch, selected } => {
+                            let batch_jobs: Vec<usize> = jobs.iter().enumerate().filter(|(_, j)| j.batch == Some(*batch)).map(|(i, _)| i).collect();
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Servers); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), batch_jobs.len()).unwrap_or(0); }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), batch_jobs.len()).unwrap_or(0); }
+                                KeyCode::Enter => {
+                                    if let Some(&job_idx) = batch_jobs.get(*selected) { jobs_list_state.select(Some(job_idx)); change_view = Some(ActiveView::Jobs); }
+                                }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::Runbooks { selected, step } => {
+                            let steps_len = config.runbooks.get(*selected).map(|r| r.steps.len()).unwrap_or(0);
+                            let last_run = runbook_runs.iter().rev().find(|r| r.runbook == *selected);
+                            match key.code {
+                                KeyCode::Esc | KeyCode::Tab => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                KeyCode::Char('4') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::Jobs); }
+                                KeyCode::Char('6') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(ActiveView::History { filter: String::new(), selected: 0 }); }
+                                KeyCode::Char('7') if key.modifiers.contains(KeyModifiers::ALT) => { change_view = Some(diag_view(diag_run.as_ref())); }
+                                KeyCode::Down => { *selected = wrap_next(Some(*selected), config.runbooks.len()).unwrap_or(0); *step = 0; }
+                                KeyCode::Up => { *selected = wrap_prev(Some(*selected), config.runbooks.len()).unwrap_or(0); *step = 0; }
+                                KeyCode::Right => { *step = wrap_next(Some(*step), steps_len).unwrap_or(0); }
+                                KeyCode::Left => { *step = wrap_prev(Some(*step), steps_len).unwrap_or(0); }
+                                KeyCode::Char('o') | KeyCode::Char('щ') => {
+                                    // Відкрити вивід кроку у вкладці Jobs
+                                    if let Some(job_id) = last_run.and_then(|r| r.step_jobs.get(*step).copied().flatten()) {
+                                        if let Some(idx) = jobs.iter().position(|j| j.id == job_id) { jobs_list_state.select(Some(idx)); change_view = Some(ActiveView::Jobs); }
+                                    }
+                                }
+                                KeyCode::Enter => {
+                                    let busy = last_run.map(|r| r.finished.is_none()).unwrap_or(false);
+                                    if let (Some(rb), false) = (config.runbooks.get(*selected), busy) {
+                                        // Кроки з confirm / dry_run / danger_level — спершу вікно підтвердження з усіма рядками запуску
+                                        match runbook_danger(rb, &config.commands) {
+                                            Some(danger) => { change_view = Some(ActiveView::Confirm { action: ConfirmAction::Runbook(*selected), name: rb.name.clone(), danger, preview: runbook_preview(rb, &config.commands, &config.targets), typed: String::new(), error: None }); }
+                                            None => { run_runbook = Some(*selected); }
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::Confirm { action, name, danger, typed, error, .. } => {
+                            let back = match action {
+                                ConfirmAction::Launch(pending) if pending.targets.is_empty() => ActiveView::Actions,
+                                ConfirmAction::Launch(_) => ActiveView::Servers,
+                                ConfirmAction::Runbook(i) => ActiveView::Runbooks { selected: *i, step: 0 },
+                            };
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(back); }
+                                KeyCode::Backspace => { typed.pop(); *error = None; }
+                                KeyCode::Char(c) if *danger == DangerLevel::Critical => { typed.push(c); *error = None; }
+                                KeyCode::Enter => {
+                                    if *danger == DangerLevel::Critical && typed.trim() != name {
+                                        *error = Some("Назва не збігається — нічого не запущено".to_string());
+                                    } else {
+                                        match action {
+                                            ConfirmAction::Launch(pending) => { launch = Some((pending.clone(), true)); }
+                                            ConfirmAction::Runbook(i) => { run_runbook = Some(*i); change_view = Some(back); }
+                                        }
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        ActiveView::Actions => {
+                            match key.code {
+                                KeyCode::Esc => { change_view = Some(ActiveView::Editor(EditorMode::Notes)); }
+                                KeyCode::Tab => { change_view = Some(ActiveView::Servers); }
+                                KeyCode::Down => { select_next(&mut list_state, config.commands.len()); }
+                                KeyCode::Up => { select_prev(&mut list_state, config.commands.len()); }
+                                KeyCode::Enter => { open_command = list_state.selected(); }
+                                _ => {}
+                            }
+                        }
+                    }
+
+                    // --- ВИБІР У ПАЛІТРІ: ті самі переходи, що й у клавіш ---
+                    if let Some(action) = pick {
+                        match action {
+                            PaletteAction::Command(i) => { list_state.select(Some(i)); change_view = Some(ActiveView::Actions); open_command = Some(i); }
+                            PaletteAction::Target(i) => { table_state.select(Some(i)); change_view = Some(ActiveView::Servers); }
+                            PaletteAction::Connect(i) => {
+                                if let Some(target) = config.targets.get(i) {
+                                    append_log_line(&mut textareas[2], &open_session(default_kind(target), target, &config.connect));
+                                    files_modified[2] = true;
+                                }
+                                table_state.select(Some(i));
+                                change_view = Some(ActiveView::Servers);
+                            }
+                            PaletteAction::Wake(i) => {
+                                let online = server_data.get(i).is_some_and(|s| s.is_online);
+                                if let Some(target) = config.targets.get(i) {
+                                    append_log_line(&mut textareas[2], &wake_target(target, online, &config.wol, &tx_to_monitor));
+                                    files_modified[2] = true;
+                                }
+                                table_state.select(Some(i));
+                                change_view = Some(ActiveView::Servers);
+                            }
+                            PaletteAction::Group(group) => {
+                                let targets = group_members(&config.targets, &group);
+                                selected_targets = targets.iter().copied().collect();
+                                if !targets.is_empty() { change_view = Some(ActiveView::TargetMenu { targets, commands: target_commands(&config.commands), selected: 0 }); }
+                            }
+                            PaletteAction::Task { id, title } => {
+                                let todo_area = &mut textareas[1];
+                                if let Some(row) = todo_area.lines().iter().position(|l| is_line_of_task(l, &id, &title)) {
+                                    todo_area.move_cursor(CursorMove::Jump(row as u16, 0));
+                                }
+                                change_view = Some(ActiveView::Editor(EditorMode::Todo));
+                            }
+                            PaletteAction::Runbook(i) => { change_view = Some(ActiveView::Runbooks { selected: i, step: 0 }); }
+                            PaletteAction::Editor(mode) => { change_view = Some(ActiveView::Editor(mode)); }
+                            PaletteAction::Jobs => { change_view = Some(ActiveView::Jobs); }
+                            PaletteAction::Diag(tool) => { change_view = Some(ActiveView::Diagnostics { tool, input: String::new(), selected: 0, marked: BTreeSet::new() }); }
+                            PaletteAction::History => { change_view = Some(ActiveView::History { filter: String::new(), selected: 0 }); }
+                            PaletteAction::Actions => { change_view = Some(ActiveView::Actions); }
+                            PaletteAction::Servers => { change_view = Some(ActiveView::Servers); }
+                            PaletteAction::Schedule => { change_view = Some(ActiveView::Schedule { selected: 0, filtering: false }); }
+                            PaletteAction::NewTask => { change_view = Some(ActiveView::TodoWizard { step: WizardStep::Title, buffer: String::new(), temp_title: String::new(), temp_desc: String::new(), temp_time: String::new(), edit: None }); }
+                            PaletteAction::Quit => break,
+                        }
+                    }
+
+                    // --- ВІДКРИТТЯ КОМАНДИ: форма параметрів або одразу запуск ---
+                    if let Some((i, cmd_struct)) = open_command.and_then(|i| config.commands.get(i).map(|c| (i, c))) {
+                        let mut params = cmd_struct.form_params();
+                        // Без виділення в таблиці сервер для `{target.*}` питаємо у формі
+                        if cmd_struct.uses_target() && !params.iter().any(|p| p.name == "target") {
+                            params.insert(0, CommandParam { name: "target".to_string(), label: Some("Сервер".to_string()), kind: ParamKind::Target, default: None, pattern: None, choices: Vec::new() });
+                        }
+                        if cmd_struct.availability != Availability::Available {
+                            append_log_line(&mut textareas[2], &unavailable_message(cmd_struct));
+                            files_modified[2] = true;
+                        } else if !params.is_empty() {
+                            change_view = Some(ActiveView::InputPopup { command_idx: i, fields: build_form(params, &config.targets), focused: 0, targets: Vec::new() });
+                        } else {
+                            launch = Some((PendingLaunch { command_idx: i, values: HashMap::new(), targets: Vec::new() }, false));
+                        }
+                    }
+
+                    // --- ЗАПУСК (після форми / меню / підтвердження) ---
+                    if let Some((mut pending, confirmed)) = launch {
+                        // Виділення з останнього редактора доступне як `{selection}` (stdin / аргументи)
+                        if let Some(text) = selected_text(&textareas[last_editor as usize]) { pending.values.entry("selection".to_string()).or_insert(text); }
+                        if let Some(cmd_struct) = config.commands.get(pending.command_idx) {
+                            let chosen: Vec<_> = pending.targets.iter().filter_map(|i| config.targets.get(*i).cloned()).collect();
+                            if cmd_struct.availability != Availability::Available {
+                                append_log_line(&mut textareas[2], &unavailable_message(cmd_struct));
+                                files_modified[2] = true;
+                                change_view = Some(ActiveView::Editor(EditorMode::Logs));
+                            } else if cmd_struct.needs_confirmation() && !confirmed {
+                                let preview = specs_for_targets(cmd_struct, &pending.values, &chosen).iter().map(|s| s.describe()).collect();
+                                change_view = Some(ActiveView::Confirm { name: cmd_struct.name.clone(), danger: cmd_struct.danger_level, action: ConfirmAction::Launch(pending), preview, typed: String::new(), error: None });
+                            } else {
+                                let (first_job, batch) = spawn_for_targets(cmd_struct, &pending.values, &chosen, &tx);
+                                focus_job = Some(first_job);
+                                change_view = Some(match batch { Some(batch) => ActiveView::BatchResults { batch, selected: 0 }, None => ActiveView::Jobs });
+                            }
+                        }
+                    }
+
+                    // --- СТАРТ RUNBOOK (одразу або після підтвердження) ---
+                    if let Some(rb) = run_runbook.and_then(|i| config.runbooks.get(i).map(|rb| (i, rb))) {
+                        let (index, rb) = rb;
+                        let id = runbook_runs.len() + 1;
+                        runbook_runs.push(RunbookRun { id, runbook: index, steps: vec![StepState::Pending; rb.steps.len()], step_jobs: vec![None; rb.steps.len()], notes: vec![String::new(); rb.steps.len()], finished: None });
+                        start_runbook(id, rb.clone(), config.commands.clone(), config.targets.clone(), tx.clone());
                     }
 
                     if let Some(new_view) = change_view { active_view = new_view; }
@@ -322,6 +968,15 @@ fn main() -> Result<()> {
 
         // --- АВТОЗБЕРЕЖЕННЯ ---
         if last_user_activity.elapsed() >= Duration::from_secs(30) {
+            // Нові завдання отримують ID, коли користувач не друкує
+            if files_modified[1] {
+                if let Some(lines) = ensure_task_ids(textareas[1].lines()) {
+                    replace_lines(&mut textareas[1], &lines);
+                    tasks = parse_tasks_from_text(&lines.join("\n"));
+                    let _ = tx_to_monitor.send(MonitorCommand::UpdateTasks(tasks.clone()));
+                    should_redraw = true;
+                }
+            }
             for (i, modified) in files_modified.iter_mut().enumerate() {
                 if *modified {
                     let text_to_save = textareas[i].lines().join("\n");
@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     net::TcpStream,
     sync::mpsc::{Receiver, Sender},
     thread,
@@ -9,34 +9,103 @@ use chrono::Local;
 use notify_rust::Notification;
 use rayon::prelude::*; // <--- ВАЖЛИВИЙ ІМПОРТ
 
-use crate::config::Target;
-use crate::types::{AppEvent, MonitorCommand, ServerStatus, Task};
+use crate::config::{AdminCommand, ScheduledCommand, Target, TargetHook};
+use crate::jobs::{failure_reason, spawn_named};
+use crate::recurrence::{due_at, due_now, is_overdue};
+use crate::reminders::load_dismissed;
+use crate::scheduler::{run_scheduled, run_task, Scheduler};
+use crate::types::{AppEvent, JobStatus, MonitorCommand, Reminder, ServerStatus, Task};
+
+// Стан хуків одного сервера (за назвою, бо список серверів може оновитись)
+#[derive(Default)]
+struct HookState {
+    seen_online: bool, // on_down лише після справжнього падіння, а не для цілі, вимкненої ще до старту
+    down_attempts: u32,
+    last_down: Option<Instant>,
+    last_up: Option<Instant>,
+}
+
+fn cooled_down(last: Option<Instant>, hook: &TargetHook) -> bool {
+    last.is_none_or(|t| t.elapsed() >= Duration::from_secs(hook.cooldown_secs))
+}
+
+// Запуск хука як звичайного job: вивід потрапляє у вкладку Jobs, підсумок — у Logs
+fn run_hook(kind: &str, hook: &TargetHook, target: &Target, commands: &[AdminCommand], tx: &Sender<AppEvent>, note: String) {
+    let timestamp = Local::now().format("%H:%M:%S");
+    let tx_done = tx.clone();
+    let kind_done = kind.to_string();
+    let result = spawn_named(&hook.command, "🛠", &hook.values, Some(target), commands, tx, move |_, spec, status| {
+        let outcome = if *status == JobStatus::Exited(0) { "ok".to_string() } else { format!("failed ({})", failure_reason(status)) };
+        let _ = tx_done.send(AppEvent::LogOutput(format!("[{}] 🛠 HOOK {}: '{}' {}", Local::now().format("%H:%M:%S"), kind_done, spec.name, outcome)));
+    });
+    let msg = match result {
+        Ok(_) => format!("[{}] 🛠 HOOK {}: running '{}' for '{}'{}", timestamp, kind, hook.command, target.name, note),
+        Err(e) => format!("[{}] 🛠 HOOK {} for '{}' skipped: {}", timestamp, kind, target.name, e),
+    };
+    let _ = tx.send(AppEvent::LogOutput(msg));
+}
+
+fn new_status(target: &Target) -> ServerStatus {
+    ServerStatus {
+        name: target.name.clone(),
+        is_online: false,
+        latency: 0,
+        history: VecDeque::from(vec![0; 20]),
+    }
+}
 
 pub fn start_monitor(
     targets: Vec<Target>,
     tasks: Vec<Task>,
+    commands: Vec<AdminCommand>,
+    schedules: Vec<ScheduledCommand>,
     tx_monitor: Sender<AppEvent>,
     rx_from_main: Receiver<MonitorCommand>,
 ) {
     thread::spawn(move || {
-        let mut statuses: Vec<ServerStatus> = targets.iter().map(|t| ServerStatus {
-            name: t.name.clone(),
-            is_online: false,
-            latency: 0,
-            history: VecDeque::from(vec![0; 20]),
-        }).collect();
+        let mut statuses: Vec<ServerStatus> = targets.iter().map(new_status).collect();
 
         let mut thread_tasks = tasks;
         let mut last_checked_minute = String::new();
         let mut current_targets = targets.clone();
         let mut previous_online_status: Vec<bool> = vec![true; current_targets.len()];
+        let mut hook_states: HashMap<String, HookState> = HashMap::new();
+        let mut wake_watch: HashMap<String, (Instant, Duration)> = HashMap::new(); // Ціль -> (коли розбудили, скільки чекати)
+
+        // Хвилинний тік тепер обслуговує і нагадування, і команди за розкладом
+        let (mut scheduler, schedule_errors) = Scheduler::new(schedules);
+        let timestamp = Local::now().format("%H:%M:%S");
+        for err in schedule_errors { let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] ⏱ Schedule skipped: {}", timestamp, err))); }
+        if scheduler.count() > 0 { let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] ⏱ {} scheduled command(s) active", timestamp, scheduler.count()))); }
+
+        // Нагадування, час яких минув, поки застосунок був вимкнений, — спрацьовують одразу при старті.
+        // run:"..." пропущеного завдання не запускаємо: лише нагадуємо, рішення за людиною
+        let started = Local::now().naive_local();
+        let dismissed = load_dismissed();
+        for task in thread_tasks.iter().filter(|t| is_overdue(t, started, &dismissed)) {
+            let due = due_at(task, started).map(|d| d.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+            Notification::new()
+                .summary(&format!("🔔 Missed reminder: {}", task.title))
+                .body(&format!("Due {}\n{}", due, task.description))
+                .appname("Admin Console")
+                .show()
+                .ok();
+            let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] 🔔 Missed reminder '{}' (due {})", timestamp, task.title, due)));
+            let _ = tx_monitor.send(AppEvent::ReminderDue(Reminder { task_id: task.id.clone(), title: task.title.clone(), description: task.description.clone(), due, missed: true }));
+        }
 
         loop {
             // 1. Оновлення конфігурації
             while let Ok(cmd) = rx_from_main.try_recv() {
                 match cmd {
-                    MonitorCommand::UpdateTargets(new_targets) => { current_targets = new_targets; }
+                    MonitorCommand::UpdateTargets(new_targets) => {
+                        // Стан переносимо за назвою; нові цілі стартують як офлайн без історії
+                        previous_online_status = new_targets.iter().map(|t| current_targets.iter().position(|c| c.name == t.name).map(|i| previous_online_status[i]).unwrap_or(true)).collect();
+                        statuses = new_targets.iter().map(|t| statuses.iter().find(|s| s.name == t.name).cloned().unwrap_or_else(|| new_status(t))).collect();
+                        current_targets = new_targets;
+                    }
                     MonitorCommand::UpdateTasks(new_tasks) => { thread_tasks = new_tasks; }
+                    MonitorCommand::WatchWake { target, timeout } => { wake_watch.insert(target, (Instant::now(), timeout)); }
                 }
             }
 
@@ -86,25 +155,72 @@ pub fn start_monitor(
                     let _ = tx_monitor.send(AppEvent::LogOutput(log_msg));
                 }
 
+                // 3. ХУКИ: on_down повторюємо з паузою, поки не вичерпано спроби; on_up — при поверненні
+                let target = &current_targets[i];
+                let state = hook_states.entry(target.name.clone()).or_default();
+                if online { state.seen_online = true; }
+                if !online && state.seen_online {
+                    if let Some(hook) = &target.on_down {
+                        if state.down_attempts < hook.max_attempts && cooled_down(state.last_down, hook) {
+                            state.down_attempts += 1;
+                            state.last_down = Some(Instant::now());
+                            let note = format!(" (attempt {}/{})", state.down_attempts, hook.max_attempts);
+                            run_hook("on_down", hook, target, &commands, &tx_monitor, note);
+                        }
+                    }
+                } else if online && !previous_online_status[i] {
+                    state.down_attempts = 0;
+                    if let Some(hook) = &target.on_up {
+                        if cooled_down(state.last_up, hook) {
+                            state.last_up = Some(Instant::now());
+                            run_hook("on_up", hook, target, &commands, &tx_monitor, String::new());
+                        }
+                    }
+                }
+
                 previous_online_status[i] = online;
             }
 
+            // Після WoL: чекаємо, поки ціль відповість, або здаємось після таймауту
+            wake_watch.retain(|name, (started, timeout)| {
+                let online = current_targets.iter().position(|t| t.name == *name).and_then(|i| statuses.get(i)).is_some_and(|s| s.is_online);
+                let timestamp = Local::now().format("%H:%M:%S");
+                if online {
+                    let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] 🟢 WoL: '{}' online через {} с", timestamp, name, started.elapsed().as_secs())));
+                    Notification::new().summary("SERVER WOKE UP ⏰").body(&format!("'{}' відповідає після Wake-on-LAN.", name)).appname("Admin Console").show().ok();
+                    false
+                } else if started.elapsed() >= *timeout {
+                    let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] 🔴 WoL: '{}' не відповів за {} с", timestamp, name, timeout.as_secs())));
+                    false
+                } else { true }
+            });
+
             let _ = tx_monitor.send(AppEvent::ServerUpdate(statuses.clone()));
 
-            // 4. Перевірка нагадувань (Tasks) - без змін
-            let current_time_str = Local::now().format("%H:%M").to_string();
+            // 4. Хвилинний тік: нагадування (Tasks) і команди за розкладом
+            let now = Local::now();
+            let current_time_str = now.format("%H:%M").to_string();
             if current_time_str != last_checked_minute {
                 for task in &thread_tasks {
-                    if !task.completed && !task.time.is_empty() && task.time == current_time_str {
-                        Notification::new()
-                            .summary(&format!("🔔 Reminder: {}", task.title))
-                            .body(&task.description)
-                            .appname("Admin Console")
-                            .show()
-                            .ok();
-                        let _ = tx_monitor.send(AppEvent::TaskCompleted(task.title.clone()));
+                    if !due_now(task, now.naive_local()) { continue; }
+                    // Завдання з run:"..." виконується саме; якщо запустити не вдалось — звичайне нагадування
+                    if task.command.is_some() {
+                        let timestamp = now.format("%H:%M:%S");
+                        match run_task(task, &commands, &current_targets, &tx_monitor) {
+                            Ok(job) => { let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] 📅 Task '{}': job #{} started", timestamp, task.title, job))); continue; }
+                            Err(e) => { let _ = tx_monitor.send(AppEvent::LogOutput(format!("[{}] 📅 Task '{}' not run: {}", timestamp, task.title, e))); }
+                        }
                     }
+                    Notification::new()
+                        .summary(&format!("🔔 Reminder: {}", task.title))
+                        .body(&task.description)
+                        .appname("Admin Console")
+                        .show()
+                        .ok();
+                    let due = now.format("%Y-%m-%d %H:%M").to_string();
+                    let _ = tx_monitor.send(AppEvent::ReminderDue(Reminder { task_id: task.id.clone(), title: task.title.clone(), description: task.description.clone(), due, missed: false }));
                 }
+                for schedule in scheduler.due(now) { run_scheduled(schedule, &commands, &current_targets, &tx_monitor); }
                 last_checked_minute = current_time_str;
             }
 
@@ -0,0 +1,51 @@
+use std::{net::UdpSocket, sync::mpsc::Sender, time::Duration};
+use chrono::Local;
+
+use crate::config::{Target, WolConfig};
+use crate::types::MonitorCommand;
+
+// "aa:bb:cc:dd:ee:ff", "AA-BB-CC-DD-EE-FF", "aabb.ccdd.eeff" або 12 hex-символів поспіль
+pub fn parse_mac(text: &str) -> Result<[u8; 6], String> {
+    let hex: String = text.chars().filter(|c| !matches!(c, ':' | '-' | '.' | ' ')).collect();
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("некоректна MAC-адреса '{}'", text));
+    }
+    let mut mac = [0u8; 6];
+    for (i, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|e| e.to_string())?;
+    }
+    Ok(mac)
+}
+
+// 6 байтів 0xFF і 16 повторів MAC
+pub fn magic_packet(mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xFF; 6];
+    for _ in 0..16 { packet.extend_from_slice(mac); }
+    packet
+}
+
+pub fn send_magic_packet(mac: &[u8; 6], broadcast: &str, port: u16) -> std::io::Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&magic_packet(mac), (broadcast, port))?;
+    Ok(())
+}
+
+// Надсилає WoL і просить монітор стежити, поки сервер не з'явиться. Повертає рядок для Logs
+pub fn wake_target(target: &Target, online: bool, wol: &WolConfig, tx_monitor: &Sender<MonitorCommand>) -> String {
+    let timestamp = Local::now().format("%H:%M:%S");
+    if online { return format!("[{}] ⏰ WoL: '{}' вже онлайн", timestamp, target.name); }
+    let Some(mac_text) = &target.mac else { return format!("[{}] ⏰ WoL: для '{}' не вказано mac у config.json", timestamp, target.name); };
+    let mac = match parse_mac(mac_text) {
+        Ok(mac) => mac,
+        Err(e) => return format!("[{}] ❌ WoL '{}': {}", timestamp, target.name, e),
+    };
+    let broadcast = target.broadcast.as_deref().unwrap_or(&wol.broadcast);
+    match send_magic_packet(&mac, broadcast, wol.port) {
+        Ok(()) => {
+            let _ = tx_monitor.send(MonitorCommand::WatchWake { target: target.name.clone(), timeout: Duration::from_secs(wol.timeout_secs) });
+            format!("[{}] ⏰ WoL → '{}' ({}) via {}:{}, чекаю до {} с", timestamp, target.name, mac_text, broadcast, wol.port, wol.timeout_secs)
+        }
+        Err(e) => format!("[{}] ❌ WoL '{}': {}", timestamp, target.name, e),
+    }
+}
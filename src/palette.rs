@@ -0,0 +1,127 @@
+use std::{collections::HashMap, fs};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::types::{DiagTool, EditorMode, PaletteAction, PaletteItem, Task};
+use crate::connect::{default_kind, kind_label};
+use crate::utils::{availability_marker, group_members, target_groups, title_with_markers};
+
+const USAGE_FILE: &str = "palette.json";
+const MAX_MATCHES: usize = 50;
+
+// Скільки разів і коли востаннє обирали пункт (для сортування "часті/нещодавні")
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct PaletteUsage {
+    pub count: u32,
+    pub last: i64, // unix-час
+}
+
+pub fn load_usage() -> HashMap<String, PaletteUsage> {
+    fs::read_to_string(USAGE_FILE).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default()
+}
+
+pub fn record_usage(usage: &mut HashMap<String, PaletteUsage>, key: &str) {
+    let entry = usage.entry(key.to_string()).or_default();
+    entry.count += 1;
+    entry.last = Local::now().timestamp();
+    let _ = fs::write(USAGE_FILE, serde_json::to_string_pretty(usage).unwrap_or_default());
+}
+
+// Усе, що вміють клавіатурні обробники: вкладки, команди, сервери, завдання, ранбуки
+pub fn build_items(config: &AppConfig, tasks: &[Task]) -> Vec<PaletteItem> {
+    let item = |key: String, kind, label: String, keys: &str, action| PaletteItem { key, kind, label, keys: keys.to_string(), dimmed: false, action };
+    let mut items = vec![
+        item("view:notes".into(), "view", "Notes".into(), "Alt+1", PaletteAction::Editor(EditorMode::Notes)),
+        item("view:todo".into(), "view", "Todo".into(), "Alt+2", PaletteAction::Editor(EditorMode::Todo)),
+        item("view:logs".into(), "view", "Logs".into(), "Alt+3", PaletteAction::Editor(EditorMode::Logs)),
+        item("view:jobs".into(), "view", "Jobs".into(), "Alt+4", PaletteAction::Jobs),
+        item("view:runbooks".into(), "view", "Runbooks".into(), "Alt+5", PaletteAction::Runbook(0)),
+        item("view:history".into(), "view", "History".into(), "Alt+6", PaletteAction::History),
+        item("diag:scan".into(), "diag", "Port scan".into(), "Alt+7", PaletteAction::Diag(DiagTool::PortScan)),
+        item("diag:dns".into(), "diag", "DNS lookup".into(), "Alt+7", PaletteAction::Diag(DiagTool::Dns)),
+        item("diag:trace".into(), "diag", "Traceroute".into(), "Alt+7", PaletteAction::Diag(DiagTool::Trace)),
+        item("diag:reverse".into(), "diag", "Reverse lookup".into(), "Alt+7", PaletteAction::Diag(DiagTool::Reverse)),
+        item("diag:discover".into(), "diag", "Discovery (subnet sweep)".into(), "Alt+7", PaletteAction::Diag(DiagTool::Discover)),
+        item("view:actions".into(), "view", "Actions".into(), "Tab", PaletteAction::Actions),
+        item("view:servers".into(), "view", "Servers".into(), "Tab Tab", PaletteAction::Servers),
+        item("view:schedule".into(), "view", "Schedule".into(), "Tab Tab Tab", PaletteAction::Schedule),
+        item("view:new-task".into(), "view", "New Task".into(), "Alt+T", PaletteAction::NewTask),
+        item("view:quit".into(), "view", "Quit".into(), "Ctrl+Q", PaletteAction::Quit),
+    ];
+    for (i, cmd) in config.commands.iter().enumerate() {
+        let marker = availability_marker(cmd);
+        items.push(PaletteItem {
+            key: format!("cmd:{}", cmd.name),
+            kind: "cmd",
+            label: cmd.name.clone(),
+            dimmed: marker.is_some(),
+            keys: marker.unwrap_or_else(|| "Actions ↵".to_string()),
+            action: PaletteAction::Command(i),
+        });
+    }
+    for (i, target) in config.targets.iter().enumerate() {
+        items.push(item(format!("srv:{}", target.name), "srv", format!("{} ({})", target.name, target.address), "Servers", PaletteAction::Target(i)));
+        if target.mac.is_some() { items.push(item(format!("wake:{}", target.name), "wake", format!("Wake {}", target.name), "Servers W", PaletteAction::Wake(i))); }
+        items.push(item(format!("connect:{}", target.name), "connect", format!("{} {}", kind_label(default_kind(target)), target.name), "Servers C", PaletteAction::Connect(i)));
+    }
+    for group in target_groups(&config.targets) {
+        items.push(item(format!("group:{}", group), "group", format!("{} ({} серверів)", group, group_members(&config.targets, &group).len()), "Servers G", PaletteAction::Group(group)));
+    }
+    for (i, rb) in config.runbooks.iter().enumerate() {
+        items.push(item(format!("runbook:{}", rb.name), "runbook", rb.name.clone(), "Alt+5", PaletteAction::Runbook(i)));
+    }
+    for task in tasks.iter().filter(|t| !t.completed) {
+        let label = if task.time.is_empty() { title_with_markers(task) } else { format!("{} {}", task.time, title_with_markers(task)) };
+        let key = if task.id.is_empty() { &task.title } else { &task.id };
+        items.push(item(format!("task:{}", key), "task", label, "Alt+2", PaletteAction::Task { id: task.id.clone(), title: task.title.clone() }));
+    }
+    items
+}
+
+// Нечіткий збіг: усі символи запиту мають трапитись у тексті по порядку.
+// Бонуси за початок слова та суцільні шматки, невеликий штраф за пропуски.
+// Пробуємо кожну позицію першого символу ("tcp" у "Test TCP" — друге слово, а не "t" з "Test")
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() { return Some(0); }
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    (0..chars.len()).filter(|&start| chars[start] == query[0]).filter_map(|start| score_from(&query, &chars, start)).max()
+}
+
+fn score_from(query: &[char], chars: &[char], start: usize) -> Option<i32> {
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev: Option<usize> = None;
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        if qi == query.len() { break; }
+        if *c != query[qi] { continue; }
+        score += 1;
+        if i == 0 || !chars[i - 1].is_alphanumeric() { score += 8; }
+        score += match prev {
+            Some(p) if p + 1 == i => 5,
+            Some(p) => -((i - p - 1) as i32).min(5),
+            None => -(i as i32).min(5),
+        };
+        prev = Some(i);
+        qi += 1;
+    }
+    if qi == query.len() { Some(score) } else { None }
+}
+
+fn frecency(usage: Option<&PaletteUsage>, now: i64) -> i32 {
+    let Some(u) = usage else { return 0 };
+    let age = now - u.last;
+    let recency = if age < 3600 { 12 } else if age < 86_400 { 6 } else if age < 7 * 86_400 { 2 } else { 0 };
+    u.count.min(10) as i32 * 2 + recency
+}
+
+// Відбір і сортування: збіг із запитом + часті/нещодавні пункти вгорі
+pub fn rank(items: Vec<PaletteItem>, query: &str, usage: &HashMap<String, PaletteUsage>) -> Vec<PaletteItem> {
+    let now = Local::now().timestamp();
+    let mut scored: Vec<(i32, PaletteItem)> = items.into_iter()
+        .filter_map(|item| fuzzy_score(query, &item.label).map(|s| (s + frecency(usage.get(&item.key), now), item)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score)); // Стабільне: при рівності лишається природний порядок
+    scored.into_iter().take(MAX_MATCHES).map(|(_, item)| item).collect()
+}
@@ -0,0 +1,372 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::reminders::DUE_FORMAT;
+use crate::types::{Reminder, Task};
+use crate::utils::{parse_tasks_from_text, task_matches};
+
+// Правило повтору з дужок завдання: `- [09:00 mon-fri] Check backups`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    Daily,
+    Weekly(Vec<u32>), // Дні тижня, 0 = понеділок
+    Monthly(u32),     // День місяця; у коротших місяцях — останній день
+    EveryHours(u32),  // Від дати й часу в дужках: 09:00, 11:00, 13:00 ...
+}
+
+const DAY_NAMES: [(&str, &str); 7] = [("mon", "пн"), ("tue", "вт"), ("wed", "ср"), ("thu", "чт"), ("fri", "пт"), ("sat", "сб"), ("sun", "нд")];
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+
+fn day_index(name: &str) -> Option<u32> {
+    DAY_NAMES.iter().position(|(en, ua)| name == *en || name == *ua).map(|i| i as u32)
+}
+
+// "mon-fri", "mon,wed,fri", "fri-mon" (через неділю), "сб,нд"
+fn parse_days(text: &str) -> Option<Vec<u32>> {
+    let mut days = Vec::new();
+    for item in text.split(',').filter(|s| !s.is_empty()) {
+        match item.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (day_index(from)?, day_index(to)?);
+                let mut day = from;
+                loop { days.push(day); if day == to { break; } day = (day + 1) % 7; }
+            }
+            None => days.push(day_index(item)?),
+        }
+    }
+    days.sort_unstable();
+    days.dedup();
+    (!days.is_empty()).then_some(days)
+}
+
+pub fn parse_recurrence(text: &str) -> Option<Recurrence> {
+    let lower = text.trim().to_lowercase();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    match words.as_slice() {
+        ["daily"] | ["щодня"] => Some(Recurrence::Daily),
+        ["weekdays"] | ["будні"] => Some(Recurrence::Weekly(vec![0, 1, 2, 3, 4])),
+        ["weekends"] | ["вихідні"] => Some(Recurrence::Weekly(vec![5, 6])),
+        ["monthly", day] => day.parse().ok().filter(|d| (1..=31).contains(d)).map(Recurrence::Monthly),
+        ["every", hours] => hours.strip_suffix('h').and_then(|h| h.parse().ok()).filter(|h| *h > 0).map(Recurrence::EveryHours),
+        ["weekly", days] | [days] => parse_days(days).map(Recurrence::Weekly),
+        _ => None,
+    }
+}
+
+// "9:00" і "09:00" -> 09:00
+pub fn parse_clock(text: &str) -> Option<NaiveTime> {
+    if !text.contains(':') { return None; }
+    NaiveTime::parse_from_str(text, TIME_FORMAT).ok()
+}
+
+// Вміст дужок "- [...]": дата (YYYY-MM-DD), час (HH:MM) і правило повтору.
+// None — якщо щось не розпізнано (тоді рядок лишається як є у `time`)
+pub fn parse_schedule(inside: &str) -> Option<(String, String, Option<Recurrence>)> {
+    let (mut date, mut time, mut rule) = (String::new(), String::new(), Vec::new());
+    for token in inside.split_whitespace() {
+        if NaiveDate::parse_from_str(token, DATE_FORMAT).is_ok() { date = token.to_string(); }
+        else if let Some(clock) = parse_clock(token) { time = clock.format(TIME_FORMAT).to_string(); }
+        else { rule.push(token); }
+    }
+    let recurrence = if rule.is_empty() { None } else { Some(parse_recurrence(&rule.join(" "))?) };
+    Some((date, time, recurrence))
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 { (date.year() + 1, 1) } else { (date.year(), date.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).and_then(|d| d.pred_opt()).map(|d| d.day()).unwrap_or(28)
+}
+
+impl Recurrence {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Daily | Recurrence::EveryHours(_) => true,
+            Recurrence::Weekly(days) => days.contains(&date.weekday().num_days_from_monday()),
+            Recurrence::Monthly(day) => date.day() == (*day).min(days_in_month(date)),
+        }
+    }
+
+    // Перше спрацювання не раніше `t` і не раніше `anchor` (дата й час з дужок)
+    pub fn at_or_after(&self, anchor: NaiveDateTime, t: NaiveDateTime) -> NaiveDateTime {
+        if let Recurrence::EveryHours(hours) = self {
+            if anchor >= t { return anchor; }
+            let step = Duration::hours(*hours as i64);
+            let passed = (t - anchor).num_minutes();
+            let steps = (passed + step.num_minutes() - 1) / step.num_minutes();
+            return anchor + step * steps as i32;
+        }
+        let mut date = t.date().max(anchor.date());
+        for _ in 0..400 {
+            let candidate = date.and_time(anchor.time());
+            if candidate >= t && candidate >= anchor && self.matches_day(date) { return candidate; }
+            date = date.succ_opt().unwrap_or(date);
+        }
+        anchor
+    }
+}
+
+fn minute_start(now: NaiveDateTime) -> NaiveDateTime {
+    now.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(now)
+}
+
+fn anchor(task: &Task, now: NaiveDateTime) -> NaiveDateTime {
+    let date = NaiveDate::parse_from_str(&task.date, DATE_FORMAT).unwrap_or(now.date());
+    let time = NaiveTime::parse_from_str(&task.time, TIME_FORMAT).unwrap_or(NaiveTime::MIN);
+    date.and_time(time)
+}
+
+// Найближче спрацювання повторюваного завдання (пропущені в минулому не враховуються)
+pub fn next_occurrence(task: &Task, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    task.recurrence.as_ref().map(|rule| rule.at_or_after(anchor(task, now), minute_start(now)))
+}
+
+// Коли завдання мало спрацювати: для повторюваних з датою — найближче неопрацьоване (у минулому, якщо пропущене).
+// None — без дати (звичайне "HH:MM" будь-якого дня) або виконане
+pub fn due_at(task: &Task, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if task.completed { return None; }
+    match &task.recurrence {
+        Some(rule) if !task.date.is_empty() => { let anchor = anchor(task, now); Some(rule.at_or_after(anchor, anchor)) }
+        Some(_) => next_occurrence(task, now),
+        None => Some(NaiveDate::parse_from_str(&task.date, DATE_FORMAT).ok()?.and_time(parse_clock(&task.time)?)),
+    }
+}
+
+// Прострочене, якщо саме цей час не відхилили (Esc у вікні нагадувань)
+pub fn is_overdue(task: &Task, now: NaiveDateTime, dismissed: &[Reminder]) -> bool {
+    due_at(task, now).is_some_and(|due| due < minute_start(now) && !dismissed.iter().any(|d| d.is_for(task) && d.due == due.format(DUE_FORMAT).to_string()))
+}
+
+// Час у панелі Schedule: як due_at, а завдання без дати — на сьогодні
+pub fn schedule_time(task: &Task, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    due_at(task, now).or_else(|| parse_clock(&task.time).map(|t| now.date().and_time(t)))
+}
+
+// Індекси завдань у порядку панелі Schedule: за датою й часом, далі за пріоритетом; без часу — далі, виконані — в кінці
+pub fn schedule_order(tasks: &[Task], now: NaiveDateTime, with_completed: bool, filter: &str) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..tasks.len()).filter(|i| (with_completed || !tasks[*i].completed) && task_matches(&tasks[*i], filter)).collect();
+    order.sort_by(|a, b| {
+        let (a, b) = (&tasks[*a], &tasks[*b]);
+        a.completed.cmp(&b.completed).then_with(|| match (schedule_time(a, now), schedule_time(b, now)) {
+            (Some(a_due), Some(b_due)) => a_due.cmp(&b_due),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }).then_with(|| b.priority.cmp(&a.priority)).then_with(|| a.title.cmp(&b.title))
+    });
+    order
+}
+
+// Введення у візарді -> вміст дужок: "14:00" (найближчі 14:00), "tomorrow 9:00", "+2h", "+1d30m",
+// "2026-10-20 14:00" або час з правилом повтору ("09:00 mon-fri"). Пусто — без часу; None — не розпізнано
+pub fn resolve_when(input: &str, now: NaiveDateTime) -> Option<String> {
+    let text = input.trim().to_lowercase();
+    if text.is_empty() { return Some(String::new()); }
+    let stamp = |at: NaiveDateTime| at.format("%Y-%m-%d %H:%M").to_string();
+
+    if let Some(relative) = text.strip_prefix('+') {
+        let (mut total, mut number) = (Duration::zero(), String::new());
+        for c in relative.chars().filter(|c| !c.is_whitespace()) {
+            if c.is_ascii_digit() { number.push(c); continue; }
+            let n: i64 = number.parse().ok()?;
+            total += match c { 'm' | 'х' => Duration::minutes(n), 'h' | 'г' => Duration::hours(n), 'd' | 'д' => Duration::days(n), _ => return None };
+            number.clear();
+        }
+        if !number.is_empty() || total.is_zero() { return None; }
+        return Some(stamp(minute_start(now) + total));
+    }
+
+    if let Some((_, time, Some(rule))) = parse_schedule(&text) {
+        if time.is_empty() && !matches!(rule, Recurrence::EveryHours(_)) { return None; }
+        return Some(text.split_whitespace().map(|t| parse_clock(t).map(|c| c.format(TIME_FORMAT).to_string()).unwrap_or_else(|| t.to_string())).collect::<Vec<_>>().join(" "));
+    }
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (day, clock) = match words.as_slice() {
+        [clock] if parse_clock(clock).is_some() => (None, parse_clock(clock)),
+        [day] => (Some(*day), None),
+        [day, clock] => (Some(*day), Some(parse_clock(clock)?)),
+        _ => return None,
+    };
+    let date = match day {
+        None => None,
+        Some("today" | "сьогодні") => Some(now.date()),
+        Some("tomorrow" | "завтра") => now.date().succ_opt(),
+        Some(other) => Some(NaiveDate::parse_from_str(other, DATE_FORMAT).ok()?),
+    };
+    // Дата без часу — на 09:00; час без дати — найближчий (сьогодні або завтра)
+    let clock = clock.unwrap_or(NaiveTime::from_hms_opt(9, 0, 0)?);
+    let date = date.unwrap_or_else(|| if now.date().and_time(clock) > now { now.date() } else { now.date().succ_opt().unwrap_or(now.date()) });
+    Some(stamp(date.and_time(clock)))
+}
+
+// Чи має нагадування спрацювати цієї хвилини
+pub fn due_now(task: &Task, now: NaiveDateTime) -> bool {
+    if task.completed { return false; }
+    match &task.recurrence {
+        Some(_) => next_occurrence(task, now) == Some(minute_start(now)),
+        None => !task.time.is_empty() && task.time == now.format(TIME_FORMAT).to_string() && (task.date.is_empty() || task.date == now.format(DATE_FORMAT).to_string()),
+    }
+}
+
+// Повторюване завдання не закривається: у дужки пишемо дату й час наступного спрацювання,
+// правило лишається як було. None — завдання звичайне
+pub fn complete_line(line: &str, now: NaiveDateTime) -> Option<String> {
+    let task = parse_tasks_from_text(line).into_iter().next()?;
+    let rule = task.recurrence.as_ref()?;
+    let anchor = anchor(&task, now);
+    // Закрили заздалегідь — пропускаємо найближче спрацювання; пропущене (застосунок був вимкнений) —
+    // переносимо на перше після поточного моменту
+    let pending = rule.at_or_after(anchor, anchor);
+    let next = rule.at_or_after(anchor, pending.max(minute_start(now)) + Duration::minutes(1));
+
+    let open = line.find("- [")? + 3;
+    let close = open + line[open..].find(']')?;
+    let rule_tokens: Vec<&str> = line[open..close].split_whitespace()
+        .filter(|t| NaiveDate::parse_from_str(t, DATE_FORMAT).is_err() && parse_clock(t).is_none())
+        .collect();
+    Some(format!("{}{} {} {}{}", &line[..open], next.format(DATE_FORMAT), next.format(TIME_FORMAT), rule_tokens.join(" "), &line[close..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn task(line: &str) -> Task {
+        parse_tasks_from_text(line).remove(0)
+    }
+
+    // Субота, 31 січня 2026, 13:20:45
+    fn now() -> NaiveDateTime {
+        at("2026-01-31 13:20") + Duration::seconds(45)
+    }
+
+    #[test]
+    fn parses_schedule() {
+        let weekdays = Some(Recurrence::Weekly(vec![0, 1, 2, 3, 4]));
+        let parsed = |date: &str, time: &str, rule| Some((date.to_string(), time.to_string(), rule));
+        let cases = [
+            ("", parsed("", "", None)),
+            ("14:00", parsed("", "14:00", None)),
+            ("9:05", parsed("", "09:05", None)),
+            ("2026-10-20 14:00", parsed("2026-10-20", "14:00", None)),
+            ("09:00 mon-fri", parsed("", "09:00", weekdays.clone())),
+            ("09:00 weekdays", parsed("", "09:00", weekdays.clone())),
+            ("09:00 будні", parsed("", "09:00", weekdays)),
+            ("fri-mon", parsed("", "", Some(Recurrence::Weekly(vec![0, 4, 5, 6])))),
+            ("сб,нд 10:00", parsed("", "10:00", Some(Recurrence::Weekly(vec![5, 6])))),
+            ("weekly mon,wed", parsed("", "", Some(Recurrence::Weekly(vec![0, 2])))),
+            ("щодня 07:30", parsed("", "07:30", Some(Recurrence::Daily))),
+            ("10:00 monthly 31", parsed("", "10:00", Some(Recurrence::Monthly(31)))),
+            ("2026-01-01 08:00 every 2h", parsed("2026-01-01", "08:00", Some(Recurrence::EveryHours(2)))),
+            ("later", None),
+            ("09:00 every 0h", None),
+            ("every 2", None),
+            ("monthly 32", None),
+            ("mon-xyz", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_schedule(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn finds_next_occurrence() {
+        let weekdays = Recurrence::Weekly(vec![0, 1, 2, 3, 4]);
+        let cases = [
+            // Включно з самим моментом, далі — наступного дня
+            (Recurrence::Daily, "2026-01-01 09:00", "2026-01-01 09:00", "2026-01-01 09:00"),
+            (Recurrence::Daily, "2026-01-01 09:00", "2026-01-01 09:01", "2026-01-02 09:00"),
+            (Recurrence::Daily, "2026-02-01 09:00", "2026-01-01 00:00", "2026-02-01 09:00"),
+            // Останній день коротшого місяця
+            (Recurrence::Monthly(31), "2026-01-31 09:00", "2026-02-01 00:00", "2026-02-28 09:00"),
+            (Recurrence::Monthly(31), "2026-01-31 09:00", "2026-02-28 09:01", "2026-03-31 09:00"),
+            (Recurrence::Monthly(30), "2028-01-30 09:00", "2028-02-01 00:00", "2028-02-29 09:00"),
+            (Recurrence::Monthly(15), "2026-01-20 09:00", "2026-01-20 00:00", "2026-02-15 09:00"),
+            // П'ятниця після часу -> понеділок
+            (weekdays.clone(), "2026-01-01 09:00", "2026-01-02 08:00", "2026-01-02 09:00"),
+            (weekdays.clone(), "2026-01-01 09:00", "2026-01-02 10:00", "2026-01-05 09:00"),
+            (Recurrence::Weekly(vec![6]), "2026-01-01 18:00", "2026-01-05 00:00", "2026-01-11 18:00"),
+            // Крок від якоря, через північ
+            (Recurrence::EveryHours(3), "2026-01-01 09:00", "2025-12-31 12:00", "2026-01-01 09:00"),
+            (Recurrence::EveryHours(3), "2026-01-01 09:00", "2026-01-01 10:00", "2026-01-01 12:00"),
+            (Recurrence::EveryHours(3), "2026-01-01 09:00", "2026-01-01 12:00", "2026-01-01 12:00"),
+            (Recurrence::EveryHours(3), "2026-01-01 09:00", "2026-01-01 23:30", "2026-01-02 00:00"),
+        ];
+        for (rule, anchor, t, expected) in cases {
+            assert_eq!(rule.at_or_after(at(anchor), at(t)), at(expected), "{:?} {} {}", rule, anchor, t);
+        }
+    }
+
+    #[test]
+    fn resolves_wizard_input() {
+        let cases = [
+            ("", Some("")),
+            ("14:00", Some("2026-01-31 14:00")),
+            ("13:00", Some("2026-02-01 13:00")),
+            ("tomorrow 9:00", Some("2026-02-01 09:00")),
+            ("завтра", Some("2026-02-01 09:00")),
+            ("today 18:30", Some("2026-01-31 18:30")),
+            ("2026-10-20 14:00", Some("2026-10-20 14:00")),
+            ("2026-10-20", Some("2026-10-20 09:00")),
+            ("+2h", Some("2026-01-31 15:20")),
+            ("+1d30m", Some("2026-02-01 13:50")),
+            ("+1г 15х", Some("2026-01-31 14:35")),
+            ("9:00 mon-fri", Some("09:00 mon-fri")),
+            ("every 2h", Some("every 2h")),
+            ("mon-fri", None),
+            ("+", None),
+            ("+2", None),
+            ("+5y", None),
+            ("someday", None),
+            ("25:00", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(resolve_when(input, now()).as_deref(), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn completes_recurring_lines() {
+        let cases = [
+            // Пропущене спрацювання -> перше після поточного моменту
+            ("- [2026-01-31 09:00 daily] Backup", Some("- [2026-02-01 09:00 daily] Backup")),
+            ("- [09:00 daily] Standup", Some("- [2026-02-01 09:00 daily] Standup")),
+            // Закрили заздалегідь -> пропускаємо найближче
+            ("- [2026-02-02 09:00 daily] Early", Some("- [2026-02-03 09:00 daily] Early")),
+            ("- [2026-01-31 09:00 monthly 31] Rent", Some("- [2026-02-28 09:00 monthly 31] Rent")),
+            ("- [2026-01-30 18:00 mon-fri] Report", Some("- [2026-02-02 18:00 mon-fri] Report")),
+            ("- [2026-01-31 09:00 every 2h] Check", Some("- [2026-01-31 15:00 every 2h] Check")),
+            ("  - [2026-01-31 09:00 daily] Backup DB !! #ops ^a1b2c3", Some("  - [2026-02-01 09:00 daily] Backup DB !! #ops ^a1b2c3")),
+            ("- [14:00] Call", None),
+            ("- [ ] Plain", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(complete_line(line, now()).as_deref(), expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn every_hours_without_clock_has_due_time() {
+        let t = task("- [every 2h] Check");
+        assert_eq!(t.time, "");
+        assert_eq!(due_at(&t, now()), Some(at("2026-01-31 14:00")));
+        assert_eq!(schedule_time(&t, now()), Some(at("2026-01-31 14:00")));
+        assert!(due_now(&t, at("2026-01-31 14:00")));
+        assert!(!due_now(&t, at("2026-01-31 15:00")));
+    }
+
+    #[test]
+    fn dismissed_due_is_not_overdue() {
+        let t = task("- [2026-01-30 14:00] Call vendor ^a1b2c3");
+        let dismissed = |due: &str| vec![Reminder { task_id: "a1b2c3".to_string(), title: String::new(), description: String::new(), due: due.to_string(), missed: true }];
+        assert!(is_overdue(&t, now(), &[]));
+        assert!(!is_overdue(&t, now(), &dismissed("2026-01-30 14:00")));
+        // Час змінили в todo.txt — старе відхилення не діє
+        assert!(is_overdue(&t, now(), &dismissed("2026-01-29 14:00")));
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::recurrence::due_at;
+use crate::types::{Reminder, Task};
+
+const SNOOZE_FILE: &str = "snoozes.json";
+const DISMISS_FILE: &str = "dismissed.json";
+const STAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub const DUE_FORMAT: &str = "%Y-%m-%d %H:%M";
+pub const SNOOZE_MINUTES: [i64; 3] = [5, 15, 60];
+
+impl Reminder {
+    // Те саме завдання: за ID, для завдань без ID — за назвою
+    pub fn same_task(&self, other: &Reminder) -> bool {
+        self.task_id == other.task_id && (!self.task_id.is_empty() || self.title == other.title)
+    }
+
+    pub fn is_for(&self, task: &Task) -> bool {
+        self.task_id == task.id && (!self.task_id.is_empty() || self.title == task.title)
+    }
+}
+
+// Відкладене нагадування: повернеться у вікно після `until`, навіть якщо застосунок перезапускали
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snooze {
+    pub reminder: Reminder,
+    pub until: String,
+}
+
+pub fn load_snoozes() -> Vec<Snooze> {
+    fs::read_to_string(SNOOZE_FILE).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default()
+}
+
+fn save_snoozes(snoozes: &[Snooze]) {
+    let _ = fs::write(SNOOZE_FILE, serde_json::to_string_pretty(snoozes).unwrap_or_default());
+}
+
+// Повертає час, до якого відкладено
+pub fn snooze(snoozes: &mut Vec<Snooze>, reminder: Reminder, minutes: i64) -> NaiveDateTime {
+    let until = Local::now().naive_local() + Duration::minutes(minutes);
+    snoozes.retain(|s| !s.reminder.same_task(&reminder));
+    snoozes.push(Snooze { reminder, until: until.format(STAMP_FORMAT).to_string() });
+    save_snoozes(snoozes);
+    until
+}
+
+pub fn is_snoozed(snoozes: &[Snooze], reminder: &Reminder) -> bool {
+    snoozes.iter().any(|s| s.reminder.same_task(reminder))
+}
+
+// Забирає ті, час яких настав (пошкоджена дата — одразу)
+pub fn take_due(snoozes: &mut Vec<Snooze>, now: NaiveDateTime) -> Vec<Reminder> {
+    let (due, waiting): (Vec<Snooze>, Vec<Snooze>) = snoozes.drain(..)
+        .partition(|s| NaiveDateTime::parse_from_str(&s.until, STAMP_FORMAT).map(|until| until <= now).unwrap_or(true));
+    *snoozes = waiting;
+    if !due.is_empty() { save_snoozes(snoozes); }
+    due.into_iter().map(|s| s.reminder).collect()
+}
+
+// Esc у вікні для одноразового завдання: цей пропущений час підтверджено, більше не нагадуємо.
+// `due` — due_at завдання; зміниться дата чи час у todo.txt — запис перестає діяти
+pub fn load_dismissed() -> Vec<Reminder> {
+    fs::read_to_string(DISMISS_FILE).ok().and_then(|data| serde_json::from_str(&data).ok()).unwrap_or_default()
+}
+
+// Записи для завдань, яких уже немає або в яких інший час, прибираємо
+pub fn dismiss(dismissed: &mut Vec<Reminder>, reminder: Reminder, tasks: &[Task], now: NaiveDateTime) {
+    dismissed.retain(|d| !d.same_task(&reminder) && tasks.iter().any(|t| d.is_for(t) && due_at(t, now).map(|due| due.format(DUE_FORMAT).to_string()) == Some(d.due.clone())));
+    dismissed.push(reminder);
+    let _ = fs::write(DISMISS_FILE, serde_json::to_string_pretty(dismissed).unwrap_or_default());
+}
+
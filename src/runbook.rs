@@ -0,0 +1,104 @@
+use std::{collections::HashMap, sync::mpsc::Sender, thread};
+use chrono::Local;
+
+use crate::config::{AdminCommand, Availability, DangerLevel, OnFailure, Runbook, Target};
+use crate::jobs::{build_spec, failure_reason, next_job_id, run_job};
+use crate::types::{AppEvent, JobStatus, StepState};
+use crate::utils::{expand_template, target_values};
+
+// Команди runbook, включно з відкатами: чи треба підтвердження перед стартом і з яким рівнем.
+// None — усі кроки безпечні
+pub fn runbook_danger(runbook: &Runbook, commands: &[AdminCommand]) -> Option<DangerLevel> {
+    let names = runbook.steps.iter().flat_map(|s| std::iter::once(&s.command).chain(s.rollback.iter()));
+    names.filter_map(|name| commands.iter().find(|c| c.name == *name))
+        .filter(|c| c.needs_confirmation())
+        .map(|c| c.danger_level)
+        .reduce(|max, level| if level > max { level } else { max })
+}
+
+// Розгорнуті рядки кроків для вікна підтвердження; `{змінні}` попередніх кроків ще невідомі й лишаються як є
+pub fn runbook_preview(runbook: &Runbook, commands: &[AdminCommand], targets: &[Target]) -> Vec<String> {
+    runbook.steps.iter().enumerate().map(|(i, step)| {
+        let label = step.label.clone().unwrap_or_else(|| step.command.clone());
+        let Some(cmd) = commands.iter().find(|c| c.name == step.command) else { return format!("{}. {}: немає команди '{}'", i + 1, label, step.command) };
+        let mut values = step.values.clone();
+        if let Some(target) = step.target.as_ref().and_then(|name| targets.iter().find(|t| t.name == *name)) { values.extend(target_values("target", target)); }
+        format!("{}. {}: {}", i + 1, label, build_spec(cmd, &values).describe())
+    }).collect()
+}
+
+// Виконує кроки по черзі у фоновому потоці; кожен крок — звичайний job
+pub fn start_runbook(run: usize, runbook: Runbook, commands: Vec<AdminCommand>, targets: Vec<Target>, tx: Sender<AppEvent>) {
+    thread::spawn(move || {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        let mut ok = true;
+
+        for (i, step) in runbook.steps.iter().enumerate() {
+            let label = step.label.clone().unwrap_or_else(|| step.command.clone());
+            let Some(cmd) = commands.iter().find(|c| c.name == step.command) else {
+                ok = false;
+                send_step(&tx, run, i, StepState::Failed(format!("немає команди '{}'", step.command)), None, None);
+                if step.on_failure == OnFailure::Continue { continue; } else { break; }
+            };
+            if cmd.availability != Availability::Available {
+                ok = false;
+                send_step(&tx, run, i, StepState::Failed("команда недоступна на цій ОС".to_string()), None, None);
+                if step.on_failure == OnFailure::Continue { continue; } else { break; }
+            }
+
+            // Змінні попередніх кроків доступні і напряму (`{var}`), і всередині values
+            let mut values = vars.clone();
+            for (k, v) in &step.values { values.insert(k.clone(), expand_template(v, &vars)); }
+            if let Some(target) = step.target.as_ref().and_then(|name| targets.iter().find(|t| t.name == *name)) {
+                values.extend(target_values("target", target));
+            }
+
+            let mut spec = build_spec(cmd, &values);
+            spec.name = format!("{} › {}", runbook.name, label);
+            spec.target = step.target.clone();
+            let job_id = next_job_id();
+            send_step(&tx, run, i, StepState::Running, Some(job_id), None);
+            let (status, stdout) = run_job(job_id, &spec, &tx);
+
+            if status == JobStatus::Exited(0) {
+                let note = step.capture.as_ref().map(|var| {
+                    let value = stdout.join("\n").trim().to_string();
+                    let shown = value.chars().take(40).collect::<String>().replace('\n', " ⏎ ");
+                    vars.insert(var.clone(), value);
+                    format!("{} = {}", var, shown)
+                });
+                send_step(&tx, run, i, StepState::Done, Some(job_id), note);
+                continue;
+            }
+
+            ok = false;
+            send_step(&tx, run, i, StepState::Failed(failure_reason(&status)), Some(job_id), None);
+            match step.on_failure {
+                OnFailure::Continue => continue,
+                OnFailure::Stop => break,
+                OnFailure::Rollback => {
+                    let rollback = step.rollback.as_ref().and_then(|name| commands.iter().find(|c| c.name == *name));
+                    let note = match rollback {
+                        Some(rb) => {
+                            let mut spec = build_spec(rb, &values);
+                            spec.name = format!("{} › rollback: {}", runbook.name, rb.name);
+                            let (rb_status, _) = run_job(next_job_id(), &spec, &tx);
+                            format!("rollback '{}': {}", rb.name, if rb_status == JobStatus::Exited(0) { "ok" } else { "FAILED" })
+                        }
+                        None => "rollback не задано".to_string(),
+                    };
+                    send_step(&tx, run, i, StepState::RolledBack, Some(job_id), Some(note));
+                    break;
+                }
+            }
+        }
+
+        let _ = tx.send(AppEvent::RunbookFinished { run, ok });
+        let icon = if ok { "✅" } else { "❌" };
+        let _ = tx.send(AppEvent::LogOutput(format!("[{}] {} Runbook '{}' {}", Local::now().format("%H:%M:%S"), icon, runbook.name, if ok { "completed" } else { "failed" })));
+    });
+}
+
+fn send_step(tx: &Sender<AppEvent>, run: usize, step: usize, state: StepState, job: Option<usize>, note: Option<String>) {
+    let _ = tx.send(AppEvent::RunbookStep { run, step, state, job, note });
+}
@@ -0,0 +1,261 @@
+use std::sync::mpsc::Sender;
+use chrono::{DateTime, Datelike, Local, Timelike};
+use notify_rust::Notification;
+
+use crate::config::{AdminCommand, ScheduledCommand, Target};
+use crate::jobs::{failure_reason, spawn_named};
+use crate::types::{AppEvent, JobStatus, Task};
+
+// Класичний 5-польовий cron: хвилина година день-місяця місяць день-тижня
+pub struct CronExpr {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>, // 0 = неділя
+    days_any: bool,
+    weekdays_any: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 { return Err(format!("cron '{}': очікується 5 полів", expr)); }
+        let mut weekdays = parse_field(fields[4], 0, 7, &["sun", "mon", "tue", "wed", "thu", "fri", "sat"])?;
+        if weekdays[7] { weekdays[0] = true; } // 7 теж неділя
+        weekdays.truncate(7);
+        Ok(CronExpr {
+            minutes: parse_field(fields[0], 0, 59, &[])?,
+            hours: parse_field(fields[1], 0, 23, &[])?,
+            days: parse_field(fields[2], 1, 31, &[])?,
+            months: parse_field(fields[3], 1, 12, &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"])?,
+            weekdays,
+            days_any: fields[2] == "*",
+            weekdays_any: fields[4] == "*",
+        })
+    }
+
+    pub fn matches(&self, dt: DateTime<Local>) -> bool {
+        let day_ok = self.days[dt.day() as usize];
+        let weekday_ok = self.weekdays[dt.weekday().num_days_from_sunday() as usize];
+        // Як у cron: якщо обмежено і день місяця, і день тижня — достатньо одного
+        let date_ok = match (self.days_any, self.weekdays_any) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        };
+        self.minutes[dt.minute() as usize] && self.hours[dt.hour() as usize] && self.months[dt.month() as usize] && date_ok
+    }
+}
+
+// Поле cron -> маска дозволених значень (індекс = значення)
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<Vec<bool>, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_lowercase();
+        if let Some(pos) = names.iter().position(|n| *n == lower) {
+            return Ok(pos as u32 + if min == 1 { 1 } else { 0 });
+        }
+        s.parse::<u32>().map_err(|_| format!("cron: невірне значення '{}'", s))
+    };
+    let mut mask = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().map_err(|_| format!("cron: невірний крок '{}'", s))?),
+            None => (part, 1),
+        };
+        if step == 0 { return Err("cron: крок не може бути 0".to_string()); }
+        let (from, to) = if range == "*" { (min, max) }
+            else if let Some((a, b)) = range.split_once('-') { (value(a)?, value(b)?) }
+            else { let v = value(range)?; (v, if part.contains('/') { max } else { v }) };
+        if from < min || to > max || from > to { return Err(format!("cron: '{}' поза межами {}-{}", part, min, max)); }
+        for v in (from..=to).step_by(step as usize) { mask[v as usize] = true; }
+    }
+    Ok(mask)
+}
+
+enum Trigger {
+    Cron(CronExpr),
+    Every(u32),
+}
+
+struct Entry {
+    schedule: ScheduledCommand,
+    trigger: Trigger,
+    last_run: DateTime<Local>,
+}
+
+// Перевіряється раз на хвилину з потоку монітора, тож працює і коли TUI просто відкритий
+pub struct Scheduler {
+    entries: Vec<Entry>,
+}
+
+impl Scheduler {
+    // Невалідні записи пропускаємо, а опис помилки повертаємо для Logs
+    pub fn new(schedules: Vec<ScheduledCommand>) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut entries = Vec::new();
+        for schedule in schedules.into_iter().filter(|s| s.enabled) {
+            let trigger = match (&schedule.cron, schedule.every_minutes) {
+                (Some(expr), _) => match CronExpr::parse(expr) {
+                    Ok(cron) => Trigger::Cron(cron),
+                    Err(e) => { errors.push(format!("'{}': {}", schedule.command, e)); continue; }
+                },
+                (None, Some(n)) if n > 0 => Trigger::Every(n),
+                _ => { errors.push(format!("'{}': потрібен cron або every_minutes > 0", schedule.command)); continue; }
+            };
+            entries.push(Entry { schedule, trigger, last_run: minute_start(Local::now()) });
+        }
+        (Scheduler { entries }, errors)
+    }
+
+    pub fn count(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Викликається один раз на початку кожної хвилини
+    pub fn due(&mut self, now: DateTime<Local>) -> Vec<ScheduledCommand> {
+        // Тік приходить трохи після межі хвилини; без округлення every_minutes: 30 спрацьовував би на 31-й
+        let now = minute_start(now);
+        let mut result = Vec::new();
+        for entry in &mut self.entries {
+            let fire = match &entry.trigger {
+                Trigger::Cron(cron) => cron.matches(now),
+                Trigger::Every(n) => (now - entry.last_run).num_minutes() >= *n as i64,
+            };
+            if fire {
+                entry.last_run = now;
+                result.push(entry.schedule.clone());
+            }
+        }
+        result
+    }
+}
+
+// Початок хвилини: секунди й наносекунди відкидаємо
+fn minute_start(dt: DateTime<Local>) -> DateTime<Local> {
+    dt.with_second(0).and_then(|d| d.with_nanosecond(0)).unwrap_or(dt)
+}
+
+// Завдання з run:"..." у свій час: команда на сервері з @target. Результат — TaskRunFinished,
+// за яким main закриває завдання (лише exit 0) і дописує підсумок у його опис.
+// Команди з confirm / dry_run / danger_level spawn_named відхиляє — лишається нагадування
+pub fn run_task(task: &Task, commands: &[AdminCommand], targets: &[Target], tx: &Sender<AppEvent>) -> Result<usize, String> {
+    let Some(command) = &task.command else { return Err("немає run:".to_string()); };
+    let target = match &task.target {
+        Some(name) => Some(targets.iter().find(|t| t.name == *name).ok_or_else(|| format!("немає сервера '{}'", name))?),
+        None => None,
+    };
+    let (task_id, title, tx_done) = (task.id.clone(), task.title.clone(), tx.clone());
+    spawn_named(command, "📅", &Default::default(), target, commands, tx, move |job, _, status| {
+        let _ = tx_done.send(AppEvent::TaskRunFinished { task_id, title, job, status: status.clone() });
+    })
+}
+
+// Запуск запланованої команди як звичайного job; ненульовий код -> тривога
+pub fn run_scheduled(schedule: ScheduledCommand, commands: &[AdminCommand], targets: &[Target], tx: &Sender<AppEvent>) {
+    let target = schedule.target.as_ref().and_then(|name| targets.iter().find(|t| t.name == *name));
+    let tx_done = tx.clone();
+    let result = spawn_named(&schedule.command, "⏱", &schedule.values, target, commands, tx, move |_, spec, status| {
+        if *status == JobStatus::Exited(0) { return; }
+        let reason = failure_reason(status);
+        let _ = tx_done.send(AppEvent::LogOutput(format!("[{}] 🔴 ALERT: Scheduled '{}' failed ({})", Local::now().format("%H:%M:%S"), spec.name, reason)));
+        Notification::new()
+            .summary("SCHEDULED JOB FAILED ⚠️")
+            .body(&format!("'{}' завершилась з помилкою: {}", spec.name, reason))
+            .appname("Admin Console")
+            .show()
+            .ok();
+    });
+    if let Err(e) = result {
+        let _ = tx.send(AppEvent::LogOutput(format!("[{}] ⏱ Schedule: {}", Local::now().format("%H:%M:%S"), e)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn cron(expr: &str) -> CronExpr {
+        CronExpr::parse(expr).unwrap()
+    }
+
+    #[test]
+    fn step_every_15_minutes() {
+        let c = cron("*/15 * * * *");
+        for mi in [0, 15, 30, 45] { assert!(c.matches(at(2026, 1, 5, 10, mi)), "{}", mi); }
+        for mi in [1, 5, 14, 59] { assert!(!c.matches(at(2026, 1, 5, 10, mi)), "{}", mi); }
+    }
+
+    #[test]
+    fn ranges_and_lists() {
+        let c = cron("0 9-17 * * *");
+        assert!(c.matches(at(2026, 1, 5, 9, 0)));
+        assert!(c.matches(at(2026, 1, 5, 17, 0)));
+        assert!(!c.matches(at(2026, 1, 5, 18, 0)));
+        assert!(!c.matches(at(2026, 1, 5, 9, 1)));
+
+        let c = cron("10-20/5,45 * * * *");
+        for mi in [10, 15, 20, 45] { assert!(c.matches(at(2026, 1, 5, 3, mi)), "{}", mi); }
+        for mi in [11, 25, 44] { assert!(!c.matches(at(2026, 1, 5, 3, mi)), "{}", mi); }
+    }
+
+    #[test]
+    fn month_and_weekday_names() {
+        let c = cron("0 9 * jan mon-fri");
+        assert!(c.matches(at(2026, 1, 5, 9, 0))); // понеділок
+        assert!(c.matches(at(2026, 1, 9, 9, 0))); // п'ятниця
+        assert!(!c.matches(at(2026, 1, 4, 9, 0))); // неділя
+        assert!(!c.matches(at(2026, 2, 2, 9, 0))); // понеділок, але лютий
+        assert!(cron("0 9 * JAN MON").matches(at(2026, 1, 5, 9, 0)));
+    }
+
+    #[test]
+    fn seven_is_sunday() {
+        for expr in ["0 0 * * 7", "0 0 * * 0", "0 0 * * sun"] {
+            let c = cron(expr);
+            assert!(c.matches(at(2026, 10, 18, 0, 0)), "{}", expr); // неділя
+            assert!(!c.matches(at(2026, 10, 19, 0, 0)), "{}", expr);
+        }
+        assert!(cron("0 0 * * 5-7").matches(at(2026, 10, 18, 0, 0)));
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        // Обидва поля обмежені — достатньо одного
+        let c = cron("0 12 15 * mon");
+        assert!(c.matches(at(2026, 1, 15, 12, 0))); // четвер, 15-те
+        assert!(c.matches(at(2026, 1, 5, 12, 0))); // понеділок
+        assert!(!c.matches(at(2026, 1, 6, 12, 0)));
+        // Одне з полів "*" — діє лише інше
+        assert!(!cron("0 12 * * mon").matches(at(2026, 1, 15, 12, 0)));
+        assert!(!cron("0 12 15 * *").matches(at(2026, 1, 5, 12, 0)));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for expr in [
+            "*/0 * * * *", "60 * * * *", "0 24 * * *", "0 0 0 * *", "0 0 32 * *", "0 0 * 13 *", "0 0 * * 8",
+            "5-1 * * * *", "x * * * *", "* * * *", "* * * * * *", "",
+        ] {
+            assert!(CronExpr::parse(expr).is_err(), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn every_minutes_does_not_drift() {
+        let schedule: ScheduledCommand = serde_json::from_str(r#"{"command": "x", "every_minutes": 30}"#).unwrap();
+        let (mut scheduler, errors) = Scheduler::new(vec![schedule]);
+        assert!(errors.is_empty());
+        scheduler.entries[0].last_run = at(2026, 1, 5, 10, 0);
+        // Тіки приходять із запізненням у кілька секунд
+        let tick = |h, mi, s| Local.with_ymd_and_hms(2026, 1, 5, h, mi, s).unwrap();
+        assert!(scheduler.due(tick(10, 29, 59)).is_empty());
+        assert_eq!(scheduler.due(tick(10, 30, 2)).len(), 1);
+        assert!(scheduler.due(tick(10, 59, 3)).is_empty());
+        assert_eq!(scheduler.due(tick(11, 0, 1)).len(), 1);
+    }
+}
@@ -1,6 +1,9 @@
-use std::collections::VecDeque;
+use std::{collections::{BTreeSet, HashMap, VecDeque}, sync::{atomic::AtomicBool, Arc}, time::Duration};
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize}; // Додали для збереження в JSON
-use crate::config::Target;
+use crate::config::{CommandParam, DangerLevel, Target};
+use crate::jobs::JobSpec;
+use crate::recurrence::Recurrence;
 #[derive(Clone)]
 pub struct ServerStatus {
     pub name: String,
@@ -12,31 +15,135 @@ pub struct ServerStatus {
 // --- НОВА СТРУКТУРА ЗАВДАННЯ ---
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Task {
-    pub title: String,
+    #[serde(default)]
+    pub id: String, // "^a1b2c3" у кінці рядка todo.txt; пусто — ще не присвоєно
+    pub title: String, // Без маркерів пріоритету і тегів
     pub description: String,
     pub time: String, // Формат "HH:MM" або пустий ""
     pub completed: bool,
+    #[serde(default)]
+    pub date: String, // "YYYY-MM-DD": для повторюваних — дата наступного спрацювання
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default)]
+    pub priority: u8, // 0..=3: "!", "!!", "!!!" у назві
+    #[serde(default)]
+    pub tags: Vec<String>, // "#ops" у назві, без '#', у нижньому регістрі
+    #[serde(default)]
+    pub target: Option<String>, // "@Dbserv2": сервер з config.targets
+    #[serde(default)]
+    pub command: Option<String>, // run:"IP Config": запускається в час завдання
+}
+
+// Нагадування, що чекає реакції у вікні: Done / Snooze / Dismiss
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Reminder {
+    #[serde(default)]
+    pub task_id: String,
+    pub title: String,
+    pub description: String,
+    pub due: String, // Коли мало спрацювати ("YYYY-MM-DD HH:MM")
+    #[serde(default)]
+    pub missed: bool, // Час минув, поки застосунок був вимкнений
+}
+
+// --- ФОНОВІ ЗАПУСКИ КОМАНД (JOBS) ---
+#[derive(Clone, PartialEq, Debug)]
+pub enum JobStatus {
+    Running,
+    Exited(i32),
+    Failed(String), // Не вдалося запустити або процес вбито сигналом
+}
+
+pub struct Job {
+    pub id: usize,
+    pub name: String,
+    pub command_line: String,
+    pub output: Vec<String>,
+    pub status: JobStatus,
+    pub started: DateTime<Local>,
+    pub duration: Option<Duration>,
+    pub target: Option<String>,
+    pub batch: Option<usize>,
+    pub spec: JobSpec, // Для історії: argv і значення форми
+}
+
+// --- РАНБУКИ ---
+#[derive(Clone, PartialEq, Debug)]
+pub enum StepState {
+    Pending,
+    Running,
+    Done,
+    Failed(String),
+    Skipped,
+    RolledBack,
+}
+
+pub struct RunbookRun {
+    pub id: usize,
+    pub runbook: usize, // Індекс у config.runbooks
+    pub steps: Vec<StepState>,
+    pub step_jobs: Vec<Option<usize>>,
+    pub notes: Vec<String>, // Захоплені змінні, результат відкату тощо
+    pub finished: Option<bool>, // Some(успіх) після завершення
 }
 
 pub enum AppEvent {
     ServerUpdate(Vec<ServerStatus>),
     LogOutput(String),
-    TaskCompleted(String),
+    ReminderDue(Reminder), // Завдання закривається лише після Done у вікні нагадування
+    TaskRunFinished { task_id: String, title: String, job: usize, status: JobStatus }, // run:"..." завдання відпрацював
+    JobStarted { id: usize, spec: Box<JobSpec> },
+    JobOutput(usize, String),
+    JobFinished { id: usize, status: JobStatus, duration: Duration },
+    RunbookStep { run: usize, step: usize, state: StepState, job: Option<usize>, note: Option<String> },
+    RunbookFinished { run: usize, ok: bool },
+    DiagRow { run: usize, key: usize, cells: Vec<String> }, // Рядок з тим самим key замінюється (hop traceroute)
+    DiagDone { run: usize, summary: String },
+}
+
+// --- ДІАГНОСТИКА ---
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum DiagTool {
+    PortScan,
+    Dns,
+    Trace,
+    Reverse,
+    Discover,
+}
+
+pub const DIAG_TOOLS: [DiagTool; 5] = [DiagTool::PortScan, DiagTool::Dns, DiagTool::Trace, DiagTool::Reverse, DiagTool::Discover];
+
+// Останній запуск діагностики: рядки таблиці приходять з фонового потоку
+pub struct DiagRun {
+    pub id: usize,
+    pub tool: DiagTool,
+    pub input: String,
+    pub rows: Vec<(usize, Vec<String>)>,
+    pub summary: Option<String>, // None — ще виконується
+    pub cancel: Arc<AtomicBool>,
 }
 
 // Команди для фонового потоку
 pub enum MonitorCommand {
-    UpdateTargets(Vec<Target>),
+    UpdateTargets(Vec<Target>), // Після імпорту з Discovery
     UpdateTasks(Vec<Task>), // Оновити список завдань у потоці
+    WatchWake { target: String, timeout: Duration }, // Після WoL: повідомити, коли сервер відповість
 }
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum EditorMode {
     Notes = 0,
     Todo = 1,
     Logs = 2,
 }
 
+// Індекси вкладок після трьох файлів
+pub const JOBS_TAB: usize = 3;
+pub const RUNBOOKS_TAB: usize = 4;
+pub const HISTORY_TAB: usize = 5;
+pub const DIAG_TAB: usize = 6;
+
 // Етапи нашого меню створення (Wizard)
 #[derive(PartialEq, Clone)]
 pub enum WizardStep {
@@ -45,23 +152,136 @@ pub enum WizardStep {
     Time,
 }
 
+// --- ПАЛІТРА КОМАНД (Ctrl+P) ---
+// Дія пункту палітри: те саме, що робить відповідна клавіша в main.rs
+#[derive(PartialEq, Clone, Debug)]
+pub enum PaletteAction {
+    Command(usize),
+    Target(usize),
+    Connect(usize), // Сесія RDP/SSH/web до сервера
+    Wake(usize),    // Wake-on-LAN
+    Diag(DiagTool),
+    Group(String),  // Запуск на всіх серверах групи
+    Task { id: String, title: String }, // Переходимо до рядка завдання в todo.txt
+    Runbook(usize),
+    Editor(EditorMode),
+    Jobs,
+    History,
+    Actions,
+    Servers,
+    Schedule,
+    NewTask,
+    Quit,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct PaletteItem {
+    pub key: String, // Стабільний ключ для статистики використання
+    pub kind: &'static str,
+    pub label: String,
+    pub keys: String, // Клавіші, що роблять те саме
+    pub dimmed: bool,
+    pub action: PaletteAction,
+}
+
+// Поле форми параметрів команди
+#[derive(PartialEq, Clone)]
+pub struct FormField {
+    pub param: CommandParam,
+    pub value: String,
+    pub error: Option<String>,
+}
+
+// Готовий до запуску виклик: значення форми вже зібрані, лишилось (можливо) підтвердити
+#[derive(PartialEq, Clone)]
+pub struct PendingLaunch {
+    pub command_idx: usize,
+    pub values: HashMap<String, String>,
+    pub targets: Vec<usize>,
+}
+
+// Що саме підтверджуємо у вікні Confirm
+#[derive(PartialEq, Clone)]
+pub enum ConfirmAction {
+    Launch(PendingLaunch),
+    Runbook(usize), // Індекс у config.runbooks: хоча б один крок потребує підтвердження
+}
+
 #[derive(PartialEq)]
 pub enum ActiveView {
     Editor(EditorMode),
     Actions,
     InputPopup {
         command_idx: usize,
-        input_buffer: String
+        fields: Vec<FormField>,
+        focused: usize,
+        targets: Vec<usize>, // Обрані в таблиці сервери (порожньо, якщо запуск з Actions)
+    },
+    // --- ТАБЛИЦЯ СЕРВЕРІВ У ФОКУСІ ---
+    Servers,
+    TargetMenu {
+        targets: Vec<usize>,
+        commands: Vec<usize>, // Індекси команд, що використовують `{target.*}`
+        selected: usize,
+    },
+    BatchResults {
+        batch: usize,
+        selected: usize,
+    },
+    Runbooks {
+        selected: usize,
+        step: usize, // Курсор у списку кроків (Enter на кроці відкриває його job)
+    },
+    // --- ПІДТВЕРДЖЕННЯ НЕБЕЗПЕЧНИХ КОМАНД ---
+    Confirm {
+        action: ConfirmAction,
+        name: String,         // Назва команди чи runbook
+        danger: DangerLevel,  // Найвищий рівень серед того, що запускаємо
+        preview: Vec<String>, // Розгорнуті рядки запуску (dry-run)
+        typed: String,        // Для Critical: введена назва
+        error: Option<String>,
     },
     Search {
         mode_return_to: EditorMode,
         query: String,
     },
+    // --- ВИВІД КОМАНД: список jobs + буфер обраного ---
+    Jobs,
+    JobSearch {
+        query: String,
+    },
+    JobSaveAs {
+        buffer: String,
+    },
+    // --- ІСТОРІЯ ЗАПУСКІВ (history.json) ---
+    History {
+        filter: String,
+        selected: usize, // Позиція у відфільтрованому списку
+    },
+    Palette {
+        query: String,
+        selected: usize,
+        matches: Vec<PaletteItem>,
+        back: EditorMode, // Куди повертає Esc (і що видно під палітрою)
+    },
+    Diagnostics {
+        tool: DiagTool,
+        input: String,
+        selected: usize,
+        marked: BTreeSet<usize>, // Ключі рядків Discovery для імпорту
+    },
     // --- НОВИЙ РЕЖИМ: СТВОРЕННЯ ЗАВДАННЯ ---
     TodoWizard {
         step: WizardStep,
         buffer: String,      // Те, що ми зараз пишемо
         temp_title: String,  // Вже введена назва
         temp_desc: String,   // Вже введений опис
+        temp_time: String,   // Підставляється на кроці часу (при редагуванні — поточний)
+        edit: Option<(String, String)>, // Some((ID, стара назва)) — редагуємо наявне завдання
+    },
+    // --- ФОКУС НА ПАНЕЛІ SCHEDULE ---
+    Schedule {
+        selected: usize, // Позиція в schedule_order
+        filtering: bool, // Друкуємо у рядок фільтра
     }
 }
\ No newline at end of file
@@ -1,24 +1,43 @@
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Table, Row, Cell, Tabs, TableState, Clear},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Table, Row, Cell, Tabs, TableState, Clear, Wrap},
     style::{Color, Modifier, Style},
 };
+use std::collections::BTreeSet;
 use tui_textarea::TextArea;
-use crate::types::{ServerStatus, ActiveView, EditorMode, Task, WizardStep};
-use crate::config::AdminCommand;
-use crate::utils::centered_rect;
+use crate::types::{ServerStatus, ActiveView, ConfirmAction, Reminder, Task, WizardStep, Job, JobStatus, RunbookRun, StepState, DiagRun, DiagTool, DIAG_TOOLS, DIAG_TAB, HISTORY_TAB, JOBS_TAB, RUNBOOKS_TAB};
+use crate::history::{filter_history, HistoryEntry};
+use crate::config::{AppConfig, DangerLevel, ParamKind};
+use crate::utils::{availability_marker, centered_rect};
+use crate::diag::{headers, tool_hint, tool_label, NEW_HOST};
+use crate::recurrence::{is_overdue, schedule_order, schedule_time};
+use crate::reminders::SNOOZE_MINUTES;
+use chrono::Local;
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw(
     f: &mut Frame,
-    textareas: &Vec<TextArea>,
-    server_data: &Vec<ServerStatus>,
-    tasks: &Vec<Task>,
+    textareas: &[TextArea],
+    server_data: &[ServerStatus],
+    tasks: &[Task],
     active_view: &ActiveView,
     table_state: &mut TableState,
     list_state: &mut ListState,
-    commands: &Vec<AdminCommand>,
-    titles: &Vec<&str>,
+    config: &AppConfig,
+    titles: &[&str],
+    jobs: &[Job],
+    jobs_list_state: &mut ListState,
+    job_viewer: &TextArea,
+    selected_targets: &BTreeSet<usize>,
+    runbook_runs: &[RunbookRun],
+    history: &[HistoryEntry],
+    diag_run: Option<&DiagRun>,
+    reminders: &[Reminder],
+    dismissed: &[Reminder], // Відхилені пропущені нагадування (dismissed.json)
+    task_filter: &str,
 ) {
+    let commands = &config.commands;
+    let servers_focused = matches!(active_view, ActiveView::Servers | ActiveView::TargetMenu { .. });
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
@@ -30,60 +49,107 @@ pub fn draw(
         .split(main_chunks[0]);
 
     // --- TABLE (SERVERS) ---
-    let header_cells = ["Server", "Ping", "Status"].iter().map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
+    let header_cells = ["", "Server", "Ping", "Status"].iter().map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
     let header = Row::new(header_cells).height(1).bottom_margin(1);
-    let rows = server_data.iter().map(|item| {
+    let rows = server_data.iter().enumerate().map(|(i, item)| {
         let ping_text = if item.is_online { format!("{}ms", item.latency) } else { "---".to_string() };
         let status_symbol = if item.is_online { "🟢" } else { "🔴" };
         let color = if !item.is_online { Color::Red } else if item.latency > 100 { Color::Yellow } else { Color::Green };
+        let mark = if selected_targets.contains(&i) { "✔" } else { "" };
         let cells = vec![
-            Cell::from(item.name.clone()),
+            Cell::from(mark).style(Style::default().fg(Color::Cyan)),
+            Cell::from(match config.targets.get(i).and_then(|t| t.group.as_ref()) {
+                Some(group) => Line::from(vec![Span::raw(item.name.clone()), Span::styled(format!(" ·{}", group), Style::default().fg(Color::DarkGray))]),
+                None => Line::from(item.name.clone()),
+            }),
             Cell::from(ping_text).style(Style::default().fg(color)),
             Cell::from(status_symbol),
         ];
         Row::new(cells).height(1)
     });
-    let table = Table::new(rows, [Constraint::Percentage(50), Constraint::Percentage(30), Constraint::Min(10)])
+    let (servers_title, servers_border) = if servers_focused { (" 📡 Servers [SPACE] [G]рупа [C]onnect [W]ake [ENTER] ", Style::default().fg(Color::Yellow)) } else { (" 📡 Servers ", Style::default()) };
+    let table = Table::new(rows, [Constraint::Length(2), Constraint::Percentage(48), Constraint::Percentage(28), Constraint::Min(8)])
         .header(header)
-        .block(Block::default().borders(Borders::ALL).title(" 📡 Servers "));
+        .row_highlight_style(if servers_focused { Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD) } else { Style::default() })
+        .block(Block::default().borders(Borders::ALL).border_style(servers_border).title(servers_title));
     f.render_stateful_widget(table, left_chunks[0], table_state);
 
     // --- SCHEDULE (LEFT BOTTOM) ---
-    let mut active_tasks: Vec<&Task> = tasks.iter().filter(|t| !t.completed).collect();
-    active_tasks.sort_by(|a, b| {
-        let a_has_time = !a.time.is_empty();
-        let b_has_time = !b.time.is_empty();
-        if a_has_time && b_has_time { a.time.cmp(&b.time) }
-        else if a_has_time { std::cmp::Ordering::Less }
-        else if b_has_time { std::cmp::Ordering::Greater }
-        else { a.title.cmp(&b.title) }
-    });
+    // У фокусі — увесь список з виконаними (їх можна повернути), інакше лише активні
+    let (schedule_selected, filtering) = if let ActiveView::Schedule { selected, filtering } = active_view { (Some(*selected), *filtering) } else { (None, false) };
+    let now = Local::now().naive_local();
+    let order = schedule_order(tasks, now, schedule_selected.is_some(), task_filter);
+    let overdue = order.iter().filter(|i| is_overdue(&tasks[**i], now, dismissed)).count();
 
     let mut items = Vec::new();
+    let mut positions = Vec::new(); // Позиція завдання у списку (з урахуванням роздільника)
     let mut first_untimed_seen = false;
 
-    for (i, task) in active_tasks.iter().take(5).enumerate() {
-        let has_time = !task.time.is_empty();
-        if i > 0 && !has_time && !first_untimed_seen {
+    for (i, task) in order.iter().map(|i| &tasks[*i]).enumerate() {
+        // Час беремо з розкладу: `every 2h` без HH:MM теж має найближче спрацювання
+        let at = schedule_time(task, now);
+        let has_time = at.is_some() || !task.time.is_empty();
+        if i > 0 && !has_time && !task.completed && !first_untimed_seen {
             items.push(ListItem::new(" ──────────────────────").style(Style::default().fg(Color::DarkGray)));
             first_untimed_seen = true;
         }
-        let (prefix, style) = if has_time {
-            (format!(" ⏰ {} │ ", task.time), Style::default().fg(Color::Yellow))
+        // Дата показується, лише коли це не сьогодні
+        let when = match at {
+            Some(at) if at.date() != now.date() => at.format("%d.%m %H:%M").to_string(),
+            Some(at) => at.format("%H:%M").to_string(),
+            None => task.time.clone(),
+        };
+        let (prefix, style) = if task.completed {
+            (format!(" ✔ {:>11} │ ", ""), Style::default().fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT))
+        } else if is_overdue(task, now, dismissed) {
+            (format!(" ⚠ {:>11} │ ", when), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        } else if task.recurrence.is_some() {
+            (format!(" 🔁 {:>11} │ ", when), Style::default().fg(Color::LightYellow))
+        } else if has_time {
+            (format!(" ⏰ {:>11} │ ", when), Style::default().fg(Color::Yellow))
         } else {
-            (" 📝  --   │ ".to_string(), Style::default().fg(Color::Cyan))
+            (format!(" 📝 {:>11} │ ", "--"), Style::default().fg(Color::Cyan))
         };
-        let title = if task.title.len() > 18 { format!("{}..", &task.title[..18]) } else { task.title.clone() };
-        items.push(ListItem::new(format!("{}{}", prefix, title)).style(style));
+        let title = if task.title.chars().count() > 18 { format!("{}..", task.title.chars().take(18).collect::<String>()) } else { task.title.clone() };
+        let mut spans = vec![Span::styled(prefix, style)];
+        if task.priority > 0 { spans.push(Span::styled(format!("{} ", "!".repeat(task.priority as usize)), style.fg(Color::LightRed).add_modifier(Modifier::BOLD))); }
+        if task.command.is_some() { spans.push(Span::styled("▶ ", style.fg(Color::LightMagenta))); }
+        spans.push(Span::styled(title, style));
+        // Живий стан пов'язаного сервера з монітора
+        if let Some(target) = &task.target {
+            let (mark, color) = match server_data.iter().find(|s| s.name == *target) {
+                Some(status) if status.is_online => ("●", Color::Green),
+                Some(_) => ("●", Color::Red),
+                None => ("?", Color::DarkGray),
+            };
+            spans.push(Span::styled(format!(" @{}{}", target, mark), style.fg(color)));
+        }
+        if !task.tags.is_empty() { spans.push(Span::styled(format!(" #{}", task.tags.join(" #")), style.fg(Color::DarkGray))); }
+        positions.push(items.len());
+        items.push(ListItem::new(Line::from(spans)));
         if !has_time { first_untimed_seen = true; }
     }
 
+    let visible = left_chunks[1].height.saturating_sub(2) as usize;
+    let mut schedule_title = " 📅 Schedule ".to_string();
+    if overdue > 0 { schedule_title.push_str(&format!("· ⚠ {} overdue ", overdue)); }
+    if items.len() > visible { schedule_title.push_str(&format!("· +{} hidden ", items.len() - visible)); }
+    let mut schedule_block = Block::default().borders(Borders::ALL).title(schedule_title);
+    if filtering || !task_filter.is_empty() {
+        let bar = if filtering { format!(" 🔎 {}_ ", task_filter) } else { format!(" 🔎 {} ({}) ", task_filter, order.len()) };
+        schedule_block = schedule_block.title(Line::from(Span::styled(bar, Style::default().fg(Color::LightCyan))).right_aligned());
+    }
+    if schedule_selected.is_some() {
+        schedule_block = schedule_block.border_style(Style::default().fg(Color::Yellow)).title_bottom(" [SPACE] Done [E]dit [T]ime [DEL] [ENTER] Рядок [/ #] Фільтр ");
+    }
     let list_widget = if items.is_empty() {
-        List::new(vec![ListItem::new("   (No active tasks)").style(Style::default().fg(Color::DarkGray))])
+        let empty = if task_filter.is_empty() { "   (No active tasks)" } else { "   (Нічого за фільтром)" };
+        List::new(vec![ListItem::new(empty).style(Style::default().fg(Color::DarkGray))])
     } else {
-        List::new(items)
+        List::new(items).highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
     };
-    f.render_widget(list_widget.block(Block::default().borders(Borders::ALL).title(" 📅 Schedule ")), left_chunks[1]);
+    let mut schedule_state = ListState::default().with_selected(schedule_selected.and_then(|s| positions.get(s.min(positions.len().saturating_sub(1))).copied()));
+    f.render_stateful_widget(list_widget.block(schedule_block), left_chunks[1], &mut schedule_state);
 
     // --- RIGHT SIDE (TABS & CONTENT) ---
     let right_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Min(0)]).split(main_chunks[1]);
@@ -91,36 +157,204 @@ pub fn draw(
     let (current_file_idx, is_actions_active) = match active_view {
         ActiveView::Editor(mode) => (*mode as usize, false),
         ActiveView::Search { mode_return_to, .. } => (*mode_return_to as usize, false),
+        ActiveView::Palette { back, .. } => (*back as usize, false),
         ActiveView::Actions => (0, true),
-        ActiveView::InputPopup { .. } => (0, true),
+        ActiveView::Confirm { action: ConfirmAction::Runbook(_), .. } => (RUNBOOKS_TAB, false),
+        ActiveView::InputPopup { .. } | ActiveView::Confirm { .. } => (0, true),
         ActiveView::TodoWizard { .. } => (1, true),
+        ActiveView::Schedule { .. } => (1, false),
+        ActiveView::Jobs | ActiveView::JobSearch { .. } | ActiveView::JobSaveAs { .. } => (JOBS_TAB, false),
+        ActiveView::Servers | ActiveView::TargetMenu { .. } | ActiveView::BatchResults { .. } => (JOBS_TAB, true),
+        ActiveView::Runbooks { .. } => (RUNBOOKS_TAB, false),
+        ActiveView::History { .. } => (HISTORY_TAB, false),
+        ActiveView::Diagnostics { .. } => (DIAG_TAB, false),
     };
 
-    let file_tabs = Tabs::new(titles.clone())
+    let file_tabs = Tabs::new(titles.to_vec())
         .block(Block::default().borders(Borders::BOTTOM))
         .select(if !is_actions_active { current_file_idx } else { 99 })
         .highlight_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD));
     f.render_widget(file_tabs, right_chunks[0]);
 
-    let action_status = if is_actions_active { Paragraph::new(" [TAB] ACTIONS ").style(Style::default().fg(Color::Black).bg(Color::Yellow)) } else { Paragraph::new(" [TAB] Actions | [ALT+T] New Task") };
+    let action_status = if servers_focused || matches!(active_view, ActiveView::BatchResults { .. }) { Paragraph::new(" [TAB] SERVERS ").style(Style::default().fg(Color::Black).bg(Color::Yellow)) }
+        else if schedule_selected.is_some() { Paragraph::new(" [TAB] SCHEDULE ").style(Style::default().fg(Color::Black).bg(Color::Yellow)) }
+        else if current_file_idx == JOBS_TAB && !is_actions_active { Paragraph::new(" ^F Пошук ^C Копія ^S Зберегти") }
+        else if current_file_idx == RUNBOOKS_TAB { Paragraph::new(" [ENTER] Старт [◀ ▶] Крок [O] Вивід") }
+        else if current_file_idx == HISTORY_TAB { Paragraph::new(" [ENTER] Повторити | Друк = фільтр") }
+        else if current_file_idx == DIAG_TAB { Paragraph::new(" [◀ ▶] Інструмент [ENTER] Старт ^C Копія") } else if is_actions_active { Paragraph::new(" [TAB] ACTIONS ").style(Style::default().fg(Color::Black).bg(Color::Yellow)) } else { Paragraph::new(" [TAB] Actions | [ALT+T] New Task | [^P]") };
     f.render_widget(action_status, Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(70), Constraint::Percentage(30)]).split(right_chunks[0])[1]);
 
     // --- CONTENT SWITCHER ---
     match active_view {
-        ActiveView::Editor(mode) | ActiveView::Search { mode_return_to: mode, .. } => {
+        ActiveView::Editor(mode) | ActiveView::Search { mode_return_to: mode, .. } | ActiveView::Palette { back: mode, .. } => {
             f.render_widget(&textareas[*mode as usize], right_chunks[1]);
         }
-        ActiveView::Actions | ActiveView::InputPopup { .. } => {
-            let items: Vec<ListItem> = commands.iter().map(|i| ListItem::new(i.name.clone()).style(Style::default().fg(Color::White))).collect();
+        ActiveView::Runbooks { selected, .. } | ActiveView::Confirm { action: ConfirmAction::Runbook(selected), .. } => {
+            // Під вікном підтвердження runbook лишається видно його кроки
+            let step = if let ActiveView::Runbooks { step, .. } = active_view { Some(*step) } else { None };
+            let rb_chunks = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(35), Constraint::Percentage(65)]).split(right_chunks[1]);
+            let items: Vec<ListItem> = config.runbooks.iter().enumerate().map(|(i, rb)| {
+                let icon = match runbook_runs.iter().rev().find(|r| r.runbook == i).map(|r| r.finished) {
+                    Some(None) => "⏳", Some(Some(true)) => "✅", Some(Some(false)) => "❌", None => "📜",
+                };
+                ListItem::new(format!("{} {}", icon, rb.name))
+            }).collect();
+            let items = if items.is_empty() { vec![ListItem::new(" (Немає runbooks у config.json)").style(Style::default().fg(Color::DarkGray))] } else { items };
+            let mut state = ListState::default().with_selected(Some(*selected));
+            f.render_stateful_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Runbooks ")).highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)), rb_chunks[0], &mut state);
+
+            if let Some(rb) = config.runbooks.get(*selected) {
+                let run = runbook_runs.iter().rev().find(|r| r.runbook == *selected);
+                let mut lines = Vec::new();
+                if !rb.description.is_empty() { lines.push(Line::from(Span::styled(format!(" {}", rb.description), Style::default().fg(Color::DarkGray)))); lines.push(Line::from("")); }
+                for (i, s) in rb.steps.iter().enumerate() {
+                    let state = run.map(|r| r.steps[i].clone()).unwrap_or(StepState::Pending);
+                    let (icon, color) = match &state {
+                        StepState::Pending => ("·", Color::DarkGray),
+                        StepState::Running => ("⏳", Color::Yellow),
+                        StepState::Done => ("✅", Color::Green),
+                        StepState::Failed(_) => ("❌", Color::Red),
+                        StepState::Skipped => ("⏭", Color::DarkGray),
+                        StepState::RolledBack => ("↩", Color::Magenta),
+                    };
+                    let label = s.label.clone().unwrap_or_else(|| s.command.clone());
+                    let mut text = format!(" {} {}. {}", icon, i + 1, label);
+                    if let StepState::Failed(reason) = &state { text.push_str(&format!("  — {}", reason)); }
+                    let style = if Some(i) == step { Style::default().fg(color).add_modifier(Modifier::REVERSED) } else { Style::default().fg(color) };
+                    lines.push(Line::from(Span::styled(text, style)));
+                    if let Some(note) = run.map(|r| &r.notes[i]).filter(|n| !n.is_empty()) {
+                        lines.push(Line::from(Span::styled(format!("      {}", note), Style::default().fg(Color::Cyan))));
+                    }
+                }
+                f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(format!(" {} ", rb.name))), rb_chunks[1]);
+            }
+        }
+        ActiveView::Actions | ActiveView::InputPopup { .. } | ActiveView::Confirm { .. } => {
+            let items: Vec<ListItem> = commands.iter().map(|i| match availability_marker(i) {
+                Some(marker) => ListItem::new(format!("{}  ({})", i.name, marker)).style(Style::default().fg(Color::DarkGray)),
+                None => ListItem::new(i.name.clone()).style(Style::default().fg(Color::White)),
+            }).collect();
             let list = List::new(items)
                 .block(Block::default().borders(Borders::ALL).title(" Оберіть команду "))
                 .highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
                 .highlight_symbol(">> ");
             f.render_stateful_widget(list, right_chunks[1], list_state);
         }
-        ActiveView::TodoWizard { .. } => {
+        ActiveView::TodoWizard { .. } | ActiveView::Schedule { .. } => {
             f.render_widget(&textareas[1], right_chunks[1]);
         }
+        ActiveView::Jobs | ActiveView::JobSearch { .. } | ActiveView::JobSaveAs { .. } | ActiveView::Servers | ActiveView::TargetMenu { .. } => {
+            draw_jobs_panel(f, right_chunks[1], jobs, jobs_list_state, job_viewer);
+        }
+        ActiveView::History { filter, selected } => {
+            let visible = filter_history(history, filter);
+            let h_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Percentage(55), Constraint::Min(5)]).split(right_chunks[1]);
+            f.render_widget(Paragraph::new(format!("> {}_", filter)).block(Block::default().borders(Borders::ALL).title(format!(" Фільтр ({}/{}) ", visible.len(), history.len()))).style(Style::default().fg(Color::Yellow)), h_chunks[0]);
+
+            let rows = visible.iter().map(|&i| {
+                let e = &history[i];
+                let (icon, color) = if e.ok() { ("✅", Color::Green) } else if e.exit.is_some() { ("⚠️", Color::LightRed) } else { ("❌", Color::Red) };
+                Row::new(vec![
+                    Cell::from(icon).style(Style::default().fg(color)),
+                    Cell::from(e.timestamp.clone()),
+                    Cell::from(e.name.clone()),
+                    Cell::from(e.exit.map(|c| c.to_string()).unwrap_or_else(|| "—".to_string())).style(Style::default().fg(color)),
+                    Cell::from(format!("{:.1}s", e.duration_ms as f32 / 1000.0)),
+                ])
+            });
+            let mut state = TableState::default().with_selected(if visible.is_empty() { None } else { Some(*selected) });
+            let table = Table::new(rows, [Constraint::Length(3), Constraint::Length(19), Constraint::Min(10), Constraint::Length(5), Constraint::Length(7)])
+                .header(Row::new(["", "Time", "Command", "Exit", "Dur"].iter().map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)))))
+                .row_highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
+                .block(Block::default().borders(Borders::ALL).title(" Історія запусків "));
+            f.render_stateful_widget(table, h_chunks[1], &mut state);
+
+            let mut lines = Vec::new();
+            if let Some(e) = visible.get(*selected).map(|&i| &history[i]) {
+                lines.push(Line::from(Span::styled(format!(" $ {}", e.argv.join(" ")), Style::default().fg(Color::White))));
+                if let Some(err) = &e.error { lines.push(Line::from(Span::styled(format!(" ⚠ {}", err), Style::default().fg(Color::Red)))); }
+                lines.push(Line::from(""));
+                for l in &e.excerpt { lines.push(Line::from(Span::styled(format!(" {}", l), Style::default().fg(Color::DarkGray)))); }
+            } else {
+                lines.push(Line::from(Span::styled("   (Історія порожня)", Style::default().fg(Color::DarkGray))));
+            }
+            f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Вивід (останні рядки) ")), h_chunks[2]);
+        }
+        ActiveView::Diagnostics { tool, input, selected, marked } => {
+            let d_chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(2), Constraint::Length(3), Constraint::Min(5), Constraint::Length(3)]).split(right_chunks[1]);
+            let tool_tabs = Tabs::new(DIAG_TOOLS.iter().map(|t| tool_label(*t)).collect::<Vec<_>>())
+                .block(Block::default().borders(Borders::BOTTOM))
+                .select(DIAG_TOOLS.iter().position(|t| t == tool).unwrap_or(0))
+                .highlight_style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD));
+            f.render_widget(tool_tabs, d_chunks[0]);
+            f.render_widget(Paragraph::new(format!("> {}_", input)).block(Block::default().borders(Borders::ALL).title(format!(" {} ", tool_hint(*tool)))).style(Style::default().fg(Color::Yellow)), d_chunks[1]);
+
+            // Таблиця — від останнього запуску, навіть якщо вже обрано інший інструмент
+            let shown = diag_run.map(|r| r.tool).unwrap_or(*tool);
+            let columns = headers(shown);
+            let widths: Vec<Constraint> = match shown {
+                DiagTool::PortScan => vec![Constraint::Length(7), Constraint::Length(7), Constraint::Min(10), Constraint::Length(8)],
+                DiagTool::Dns => vec![Constraint::Percentage(30), Constraint::Length(6), Constraint::Length(7), Constraint::Min(10)],
+                DiagTool::Trace => vec![Constraint::Length(3), Constraint::Length(16), Constraint::Min(10), Constraint::Length(5), Constraint::Length(7), Constraint::Length(7), Constraint::Length(7), Constraint::Length(7)],
+                DiagTool::Reverse => vec![Constraint::Length(40), Constraint::Min(10)],
+                DiagTool::Discover => vec![Constraint::Length(2), Constraint::Length(16), Constraint::Percentage(30), Constraint::Length(16), Constraint::Min(10)],
+            };
+            let rows = diag_run.map(|r| r.rows.as_slice()).unwrap_or_default().iter().map(|(key, cells)| {
+                let color = match (shown, cells.get(3).map(|s| s.as_str())) {
+                    (DiagTool::Trace, Some("0%")) => Color::Green,
+                    (DiagTool::Trace, Some("100%")) => Color::Red,
+                    (DiagTool::Trace, Some(_)) => Color::Yellow,
+                    (DiagTool::Discover, Some(NEW_HOST)) => Color::LightGreen,
+                    (DiagTool::Discover, Some(s)) if s.starts_with('✗') => Color::Red,
+                    _ => Color::White,
+                };
+                let mut row: Vec<Cell> = cells.iter().map(|c| Cell::from(c.clone())).collect();
+                if shown == DiagTool::Discover { row.insert(0, Cell::from(if marked.contains(key) { "✔" } else { "" }).style(Style::default().fg(Color::Cyan))); }
+                Row::new(row).style(Style::default().fg(color))
+            });
+            let title = match diag_run {
+                Some(r) => format!(" {} {} ", tool_label(r.tool), r.input),
+                None => " Результати ".to_string(),
+            };
+            let mut state = TableState::default().with_selected(diag_run.filter(|r| !r.rows.is_empty()).map(|_| *selected));
+            let table = Table::new(rows, widths)
+                .header(Row::new(std::iter::once("").filter(|_| shown == DiagTool::Discover).chain(columns.iter().copied()).map(|h| Cell::from(h).style(Style::default().fg(Color::Yellow)))))
+                .row_highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
+                .block(Block::default().borders(Borders::ALL).title(title));
+            f.render_stateful_widget(table, d_chunks[2], &mut state);
+
+            let (status, color) = match diag_run.map(|r| &r.summary) {
+                Some(Some(summary)) if shown == DiagTool::Discover && !summary.starts_with('❌') => (format!("{}  [INS] Позначити ^A Усі нові ^S Імпорт", summary), Color::Green),
+                Some(Some(summary)) => (summary.clone(), if summary.starts_with('❌') { Color::Red } else { Color::Green }),
+                Some(None) => (" ⏳ Виконується... [Esc] Зупинити".to_string(), Color::Yellow),
+                None => (" Введіть адресу або назву сервера і натисніть Enter".to_string(), Color::DarkGray),
+            };
+            f.render_widget(Paragraph::new(status).style(Style::default().fg(color)).block(Block::default().borders(Borders::ALL)), d_chunks[3]);
+        }
+        ActiveView::BatchResults { batch, selected } => {
+            let header = Row::new(["Server", "Status", "Exit", "Time", "Last line"].iter().map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)))).bottom_margin(1);
+            let batch_jobs: Vec<&Job> = jobs.iter().filter(|j| j.batch == Some(*batch)).collect();
+            let done = batch_jobs.iter().filter(|j| j.status != JobStatus::Running).count();
+            let rows = batch_jobs.iter().map(|job| {
+                let (icon, color) = job_status_style(&job.status);
+                let exit = match &job.status { JobStatus::Exited(code) => code.to_string(), JobStatus::Failed(_) => "—".to_string(), JobStatus::Running => "".to_string() };
+                let time = job.duration.map(|d| format!("{:.1}s", d.as_secs_f32())).unwrap_or_default();
+                let last = job.output.iter().rev().find(|l| !l.trim().is_empty()).cloned().unwrap_or_default();
+                Row::new(vec![
+                    Cell::from(job.target.clone().unwrap_or_default()),
+                    Cell::from(icon).style(Style::default().fg(color)),
+                    Cell::from(exit).style(Style::default().fg(color)),
+                    Cell::from(time),
+                    Cell::from(last),
+                ])
+            });
+            let mut state = TableState::default().with_selected(Some(*selected));
+            let table = Table::new(rows, [Constraint::Percentage(25), Constraint::Length(6), Constraint::Length(5), Constraint::Length(7), Constraint::Min(10)])
+                .header(header)
+                .row_highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
+                .block(Block::default().borders(Borders::ALL).title(format!(" Пакет #{}: {}/{} готово [ENTER] вивід ", batch, done, batch_jobs.len())));
+            f.render_stateful_widget(table, right_chunks[1], &mut state);
+        }
     }
 
     // --- POPUPS ---
@@ -129,20 +363,168 @@ pub fn draw(
         f.render_widget(Clear, search_area);
         f.render_widget(Paragraph::new(format!("Search: {}", query)).block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Cyan))).style(Style::default().fg(Color::Yellow).bg(Color::Black)), search_area);
     }
-    if let ActiveView::InputPopup { input_buffer, .. } = active_view {
+    if let ActiveView::TargetMenu { targets, commands: menu_commands, selected } = active_view {
+        let names: Vec<String> = targets.iter().filter_map(|i| server_data.get(*i).map(|s| s.name.clone())).collect();
+        let items: Vec<ListItem> = if menu_commands.is_empty() {
+            vec![ListItem::new(" (Немає команд з {target.*} у config.json)").style(Style::default().fg(Color::DarkGray))]
+        } else {
+            menu_commands.iter().filter_map(|i| commands.get(*i)).map(|c| ListItem::new(c.name.clone())).collect()
+        };
+        let area = centered_rect(50, 40, f.area());
+        let mut state = ListState::default().with_selected(Some(*selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(format!(" {} ", names.join(", "))))
+                .style(Style::default().fg(Color::White).bg(Color::Black))
+                .highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD))
+                .highlight_symbol(">> "),
+            area,
+            &mut state,
+        );
+    }
+    if let ActiveView::Confirm { name, danger, preview, typed, error, .. } = active_view {
+        let (title, color) = match danger {
+            DangerLevel::Critical => (" ☠ НЕБЕЗПЕЧНА КОМАНДА ", Color::Red),
+            DangerLevel::Caution => (" ⚠ Підтвердіть запуск ", Color::Yellow),
+            DangerLevel::Safe => (" Попередній перегляд ", Color::Cyan),
+        };
+        let mut lines = vec![
+            Line::from(Span::styled(format!(" {}", name), Style::default().fg(color).add_modifier(Modifier::BOLD))),
+            Line::from(""),
+        ];
+        for cmd_line in preview.iter().take(8) { lines.push(Line::from(Span::styled(format!(" $ {}", cmd_line), Style::default().fg(Color::White)))); }
+        if preview.len() > 8 { lines.push(Line::from(Span::styled(format!("   … ще {}", preview.len() - 8), Style::default().fg(Color::DarkGray)))); }
+        lines.push(Line::from(""));
+        if *danger == DangerLevel::Critical {
+            lines.push(Line::from(" Введіть назву для підтвердження:"));
+            lines.push(Line::from(Span::styled(format!(" > {}_", typed), Style::default().fg(Color::Yellow))));
+        } else {
+            lines.push(Line::from(Span::styled(" [Enter] Запустити  [Esc] Скасувати", Style::default().fg(Color::DarkGray))));
+        }
+        if let Some(err) = error { lines.push(Line::from(Span::styled(format!(" ⚠ {}", err), Style::default().fg(Color::Red)))); }
+
+        let area = centered_rect(70, 45, f.area());
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(color)).title(title)).style(Style::default().bg(Color::Black)), area);
+    }
+    if let ActiveView::JobSearch { query } = active_view {
+        let search_area = Layout::default().direction(Direction::Vertical).constraints([Constraint::Min(0), Constraint::Length(3)]).split(right_chunks[1])[1];
+        f.render_widget(Clear, search_area);
+        f.render_widget(Paragraph::new(format!("Search: {}", query)).block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Cyan))).style(Style::default().fg(Color::Yellow).bg(Color::Black)), search_area);
+    }
+    if let ActiveView::JobSaveAs { buffer } = active_view {
         let area = centered_rect(60, 20, f.area());
         f.render_widget(Clear, area);
-        f.render_widget(Paragraph::new(input_buffer.clone()).block(Block::default().borders(Borders::ALL).title(" Введіть аргумент (IP/Host) ")).style(Style::default().fg(Color::Yellow).bg(Color::Black)), area);
+        f.render_widget(Paragraph::new(format!("Шлях до файлу:\n\n> {}", buffer)).block(Block::default().borders(Borders::ALL).title(" Зберегти вивід як ")).style(Style::default().fg(Color::Yellow).bg(Color::Black)), area);
+    }
+    if let ActiveView::InputPopup { command_idx, fields, focused, targets } = active_view {
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, field) in fields.iter().enumerate() {
+            let label = field.param.label.clone().unwrap_or_else(|| field.param.name.clone());
+            let is_list = matches!(field.param.kind, ParamKind::Choice | ParamKind::Target);
+            let value = if is_list { format!("◀ {} ▶", field.value) } else { field.value.clone() };
+            let style = if i == *focused { Style::default().fg(Color::Black).bg(Color::Yellow) } else { Style::default().fg(Color::Yellow) };
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {} ({:?}): ", label, field.param.kind), Style::default().fg(Color::Cyan)),
+                Span::styled(format!("{}{}", value, if i == *focused && !is_list { "_" } else { "" }), style),
+            ]));
+            if let Some(err) = &field.error { lines.push(Line::from(Span::styled(format!("   ⚠ {}", err), Style::default().fg(Color::Red)))); }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(" [Tab] Далі  [◀ ▶] Вибір  [↑↓] Попередні  [Enter] Запуск  [Esc] Скасувати", Style::default().fg(Color::DarkGray))));
+
+        let names: Vec<String> = targets.iter().filter_map(|i| server_data.get(*i).map(|s| s.name.clone())).collect();
+        let title = match commands.get(*command_idx) {
+            Some(c) if !names.is_empty() => format!(" {} @ {} ", c.name, names.join(", ")),
+            Some(c) => format!(" {} ", c.name),
+            None => String::new(),
+        };
+        let area = centered_rect(60, 40, f.area());
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)).style(Style::default().bg(Color::Black)), area);
+    }
+    if let ActiveView::Palette { query, selected, matches, .. } = active_view {
+        let area = centered_rect(60, 60, f.area());
+        let chunks = Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(3), Constraint::Min(0)]).split(area);
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(format!("> {}_", query)).block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Cyan)).title(" Палітра команд ")).style(Style::default().fg(Color::Yellow).bg(Color::Black)), chunks[0]);
+        let items: Vec<ListItem> = if matches.is_empty() {
+            vec![ListItem::new(" (Нічого не знайдено)").style(Style::default().fg(Color::DarkGray))]
+        } else {
+            matches.iter().map(|item| {
+                let kind_color = match item.kind { "cmd" => Color::Green, "srv" => Color::Cyan, "task" => Color::Yellow, "runbook" => Color::Magenta, "group" => Color::LightCyan, "connect" => Color::LightGreen, "diag" => Color::LightYellow, _ => Color::Blue };
+                let label_style = if item.dimmed { Style::default().fg(Color::DarkGray) } else { Style::default().fg(Color::White) };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(" {:<8}", item.kind), Style::default().fg(kind_color)),
+                    Span::styled(item.label.clone(), label_style),
+                    Span::styled(format!("  {}", item.keys), Style::default().fg(Color::DarkGray)),
+                ]))
+            }).collect()
+        };
+        let mut state = ListState::default().with_selected(Some(*selected));
+        f.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title_bottom(" [↑↓] Вибір  [Enter] Виконати  [Esc] Закрити "))
+                .style(Style::default().bg(Color::Black))
+                .highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)),
+            chunks[1],
+            &mut state,
+        );
     }
-    if let ActiveView::TodoWizard { step, buffer, temp_title, .. } = active_view {
+    if let ActiveView::TodoWizard { step, buffer, temp_title, edit, .. } = active_view {
         let area = centered_rect(60, 20, f.area());
         f.render_widget(Clear, area);
         let (title, content) = match step {
-            WizardStep::Title => (" 1/3: Назва завдання ", format!("Введіть назву:\n\n> {}", buffer)),
+            WizardStep::Title => (" 1/3: Назва завдання ", format!("Введіть назву (можна !! пріоритет, #тег, @Сервер, run:\"Команда\"):\n\n> {}", buffer)),
             WizardStep::Description => (" 2/3: Опис ", format!("Назва: {}\n\nВведіть опис (можна пустий):\n> {}", temp_title, buffer)),
-            WizardStep::Time => (" 3/3: Час нагадування ", format!("Назва: {}\n\nВведіть час (HH:MM) або Enter щоб пропустити:\n> {}", temp_title, buffer)),
+            WizardStep::Time => (" 3/3: Час нагадування ", format!("Назва: {}\n\nВведіть час: 14:00, tomorrow 9:00, +2h, 2026-10-20 14:00,\nз повтором (09:00 mon-fri, 09:00 monthly 15, every 2h) або Enter щоб пропустити:\n> {}", temp_title, buffer)),
         };
+        let title = if edit.is_some() { format!(" ✎{}", title) } else { title.to_string() };
         let block = Paragraph::new(content).block(Block::default().borders(Borders::ALL).title(title)).style(Style::default().fg(Color::Cyan).bg(Color::Black));
         f.render_widget(block, area);
     }
-}
\ No newline at end of file
+    // Нагадування — поверх усього, доки хтось не відреагує
+    if let Some(reminder) = reminders.first() {
+        let color = if reminder.missed { Color::Red } else { Color::Yellow };
+        let mut lines = vec![
+            Line::from(Span::styled(format!(" {}", reminder.title), Style::default().fg(color).add_modifier(Modifier::BOLD))),
+            Line::from(Span::styled(format!(" {} {}", if reminder.missed { "Пропущено, мало бути" } else { "Час:" }, reminder.due), Style::default().fg(Color::DarkGray))),
+            Line::from(""),
+        ];
+        if !reminder.description.is_empty() { lines.push(Line::from(format!(" {}", reminder.description))); lines.push(Line::from("")); }
+        let snoozes: Vec<String> = SNOOZE_MINUTES.iter().enumerate().map(|(i, m)| format!("[{}] {} хв", i + 1, m)).collect();
+        lines.push(Line::from(Span::styled(format!(" [Enter/D] Done  {}  [Esc] Dismiss", snoozes.join("  ")), Style::default().fg(Color::DarkGray))));
+        let title = if reminders.len() > 1 { format!(" 🔔 Нагадування (ще {}) ", reminders.len() - 1) } else { " 🔔 Нагадування ".to_string() };
+        let area = centered_rect(60, 30, f.area());
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(color)).title(title)).style(Style::default().bg(Color::Black)), area);
+    }
+}
+
+fn job_status_style(status: &JobStatus) -> (&'static str, Color) {
+    match status {
+        JobStatus::Running => ("⏳", Color::Yellow),
+        JobStatus::Exited(0) => ("✅", Color::Green),
+        JobStatus::Exited(_) => ("⚠️", Color::LightRed),
+        JobStatus::Failed(_) => ("❌", Color::Red),
+    }
+}
+
+fn draw_jobs_panel(f: &mut Frame, area: Rect, jobs: &[Job], jobs_list_state: &mut ListState, job_viewer: &TextArea) {
+    let job_chunks = Layout::default().direction(Direction::Horizontal).constraints([Constraint::Percentage(35), Constraint::Percentage(65)]).split(area);
+    let items: Vec<ListItem> = jobs.iter().map(|job| {
+        let (icon, color) = job_status_style(&job.status);
+        ListItem::new(format!("{} #{} {} {}", icon, job.id, job.started.format("%H:%M:%S"), job.name)).style(Style::default().fg(color))
+    }).collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(" Jobs [Del] "))
+        .highlight_style(Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(list, job_chunks[0], jobs_list_state);
+
+    if jobs_list_state.selected().and_then(|i| jobs.get(i)).is_some() {
+        f.render_widget(job_viewer, job_chunks[1]);
+    } else {
+        f.render_widget(Paragraph::new("   (Ще нічого не запускали)").style(Style::default().fg(Color::DarkGray)).block(Block::default().borders(Borders::ALL)), job_chunks[1]);
+    }
+}
@@ -1,5 +1,11 @@
-use ratatui::prelude::*;
-use crate::types::Task;
+use chrono::{Local, NaiveDateTime};
+use ratatui::{prelude::*, widgets::ListState};
+use tui_textarea::{CursorMove, TextArea};
+use std::{collections::{BTreeSet, HashMap, HashSet}, env, hash::{BuildHasher, Hasher, RandomState}, net::IpAddr, ops::Range, path::{Path, PathBuf}};
+use regex::Regex;
+use crate::config::{AdminCommand, Availability, CommandParam, ParamKind, Target};
+use crate::types::{FormField, Job, JobStatus, Task};
+use crate::recurrence::{complete_line, parse_schedule};
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -21,21 +27,424 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         .split(popup_layout[1])[1]
 }
 
-// Перевірка, чи ввів користувач правильний час (HH:MM)
-pub fn is_valid_time(input: &str) -> bool {
-    let s = input.trim();
-    if s.is_empty() { return true; } // Пустий час дозволяємо
+// Навігація по списку з переходом через край (як у меню Actions)
+pub fn wrap_next(selected: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 { return None; }
+    Some(match selected { Some(i) => if i >= len - 1 { 0 } else { i + 1 }, None => 0 })
+}
+
+pub fn wrap_prev(selected: Option<usize>, len: usize) -> Option<usize> {
+    if len == 0 { return None; }
+    Some(match selected { Some(i) => if i == 0 { len - 1 } else { i - 1 }, None => 0 })
+}
+
+pub fn select_next(state: &mut ListState, len: usize) {
+    if len > 0 { state.select(wrap_next(state.selected(), len)); }
+}
+
+pub fn select_prev(state: &mut ListState, len: usize) {
+    if len > 0 { state.select(wrap_prev(state.selected(), len)); }
+}
+
+// Дописує рядок у кінець журналу, незалежно від того, де стоїть курсор
+pub fn append_log_line(log: &mut TextArea, line: &str) {
+    log.cancel_selection();
+    log.move_cursor(CursorMove::Bottom);
+    log.move_cursor(CursorMove::End);
+    if !log.lines().last().map(|l| l.is_empty()).unwrap_or(true) { log.insert_newline(); }
+    log.insert_str(line);
+}
+
+// Замінює весь текст, зберігаючи налаштування, історію undo і позицію курсора
+pub fn replace_lines(area: &mut TextArea, lines: &[String]) {
+    let (row, col) = area.cursor();
+    area.select_all();
+    area.insert_str(lines.join("\n"));
+    area.move_cursor(CursorMove::Jump(row as u16, col as u16));
+}
+
+// Однорядковий підсумок job для вкладки Logs (сам вивід живе у вкладці Jobs)
+pub fn format_job_summary(job: &Job) -> String {
+    let secs = job.duration.map(|d| d.as_secs_f32()).unwrap_or(0.0);
+    let (icon, result) = match &job.status {
+        JobStatus::Running => ("⏳", "running".to_string()),
+        JobStatus::Exited(0) => ("✅", "exit 0".to_string()),
+        JobStatus::Exited(code) => ("⚠️", format!("exit {}", code)),
+        JobStatus::Failed(reason) => ("❌", reason.clone()),
+    };
+    format!("[{}] {} Job #{} '{}' → {} ({:.1}s, {} lines)", Local::now().format("%H:%M:%S"), icon, job.id, job.name, result, secs, job.output.len())
+}
+
+// Назви груп серверів без повторів, за алфавітом
+pub fn target_groups(targets: &[Target]) -> Vec<String> {
+    let groups: BTreeSet<String> = targets.iter().filter_map(|t| t.group.clone()).collect();
+    groups.into_iter().collect()
+}
+
+pub fn group_members(targets: &[Target], group: &str) -> Vec<usize> {
+    targets.iter().enumerate().filter(|(_, t)| t.group.as_deref() == Some(group)).map(|(i, _)| i).collect()
+}
+
+// Команди для контекстного меню серверів: ті, що використовують `{target.*}` і можуть тут запуститись
+pub fn target_commands(commands: &[AdminCommand]) -> Vec<usize> {
+    commands.iter().enumerate().filter(|(_, c)| c.uses_target() && c.availability != Availability::Unsupported).map(|(i, _)| i).collect()
+}
+
+// "192.168.0.1:3389" -> ("192.168.0.1", "3389"); "[::1]:22" -> ("::1", "22")
+pub fn split_address(address: &str) -> (String, String) {
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.starts_with('[') => (host.trim_matches(|c| c == '[' || c == ']').to_string(), port.to_string()),
+        _ => (address.to_string(), String::new()),
+    }
+}
+
+// Значення для `{p}`, `{p.name}`, `{p.address}`, `{p.host}`, `{p.port}`
+pub fn target_values(prefix: &str, target: &Target) -> Vec<(String, String)> {
+    let (host, port) = split_address(&target.address);
+    vec![
+        (prefix.to_string(), host.clone()),
+        (format!("{}.name", prefix), target.name.clone()),
+        (format!("{}.address", prefix), target.address.clone()),
+        (format!("{}.host", prefix), host),
+        (format!("{}.port", prefix), port),
+    ]
+}
+
+// Підставляє лише відомі `{key}`; інші фігурні дужки (PowerShell-блоки) не чіпаємо.
+// Один прохід зліва направо: значення вставляються як є, `{...}` усередині значень не розгортаються
+pub fn expand_template(template: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '%']) {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let placeholder = if let Some(after) = rest.strip_prefix('{') {
+            after.find('}').map(|end| &after[..end]).filter(|key| !key.contains('{')).and_then(|key| values.get(key).map(|v| (v, key.len() + 2)))
+        } else {
+            values.get("INPUT").filter(|_| rest.starts_with("%INPUT%")).map(|v| (v, "%INPUT%".len()))
+        };
+        match placeholder {
+            Some((value, len)) => { result.push_str(value); rest = &rest[len..]; }
+            None => { result.push_str(&rest[..1]); rest = &rest[1..]; }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// Позначка для недоступної команди (у списку Actions та в Logs)
+pub fn availability_marker(cmd: &AdminCommand) -> Option<String> {
+    match cmd.availability {
+        Availability::Available => None,
+        Availability::Unsupported if cmd.only_on.is_empty() => Some(format!("немає варіанту для {}", env::consts::OS)),
+        Availability::Unsupported => Some(format!("лише {}", cmd.only_on.join("/"))),
+        Availability::NotFound => Some(format!("не знайдено: {}", cmd.local_program())),
+    }
+}
+
+pub fn unavailable_message(cmd: &AdminCommand) -> String {
+    format!("[{}] ⛔ '{}' не запущено: {}", Local::now().format("%H:%M:%S"), cmd.name, availability_marker(cmd).unwrap_or_default())
+}
 
-    let parts: Vec<&str> = s.split(':').collect();
-    if parts.len() != 2 { return false; }
+// Аналог `which`: шлях до програми або None (на Windows враховуємо PATHEXT)
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) && Path::new(program).extension().is_none() {
+        env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string()).split(';').map(|e| e.to_string()).collect()
+    } else {
+        vec![String::new()]
+    };
+    let is_runnable = |p: &Path| -> bool {
+        #[cfg(unix)]
+        { use std::os::unix::fs::PermissionsExt; p.metadata().map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false) }
+        #[cfg(not(unix))]
+        { p.is_file() }
+    };
 
-    let h = parts[0].parse::<u8>();
-    let m = parts[1].parse::<u8>();
+    if program.contains('/') || program.contains('\\') {
+        return extensions.iter().map(|ext| PathBuf::from(format!("{}{}", program, ext))).find(|p| is_runnable(p));
+    }
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}{}", program, ext))))
+        .find(|p| is_runnable(p))
+}
+
+// `${VAR}` -> значення з оточення цього процесу (порожньо, якщо змінної немає)
+pub fn expand_env_vars(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        match rest[start + 2..].find('}') {
+            Some(end) => {
+                let name = &rest[start + 2..start + 2 + end];
+                result.push_str(&env::var(name).unwrap_or_default());
+                rest = &rest[start + 2 + end + 1..];
+            }
+            None => { result.push_str(&rest[start..]); rest = ""; }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// Текст виділення в редакторі (колонки в tui-textarea рахуються в символах)
+pub fn selected_text(textarea: &TextArea) -> Option<String> {
+    let ((r1, c1), (r2, c2)) = textarea.selection_range()?;
+    let lines = textarea.lines();
+    let slice = |line: &str, from: usize, to: Option<usize>| -> String {
+        let chars = line.chars().skip(from);
+        match to { Some(to) => chars.take(to.saturating_sub(from)).collect(), None => chars.collect() }
+    };
+    if r1 == r2 { return Some(slice(&lines[r1], c1, Some(c2))); }
+    let mut parts = vec![slice(&lines[r1], c1, None)];
+    parts.extend(lines[r1 + 1..r2].iter().cloned());
+    parts.push(slice(&lines[r2], 0, Some(c2)));
+    Some(parts.join("\n"))
+}
+
+pub fn is_valid_hostname(s: &str) -> bool {
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty() && s.len() <= 253 && s.split('.').all(|label| {
+        !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
 
-    match (h, m) {
-        (Ok(hours), Ok(minutes)) => hours < 24 && minutes < 60,
-        _ => false,
+// Перевірка значення поля форми; Err містить текст для показу під полем
+pub fn validate_param(param: &CommandParam, value: &str, targets: &[Target]) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() { return Err("Поле не може бути порожнім".to_string()); }
+    match param.kind {
+        ParamKind::String => {}
+        ParamKind::Int => { if value.parse::<i64>().is_err() { return Err("Очікується ціле число".to_string()); } }
+        ParamKind::Ip => { if value.parse::<IpAddr>().is_err() { return Err("Невірна IP-адреса".to_string()); } }
+        ParamKind::Hostname => { if value.parse::<IpAddr>().is_err() && !is_valid_hostname(value) { return Err("Невірне ім'я хоста".to_string()); } }
+        ParamKind::Choice => { if !param.choices.iter().any(|c| c == value) { return Err(format!("Допустимо: {}", param.choices.join(", "))); } }
+        ParamKind::Target => { if !targets.iter().any(|t| t.name == value) { return Err("Немає такого сервера в targets".to_string()); } }
     }
+    if let Some(pattern) = &param.pattern {
+        match Regex::new(&format!("^(?:{})$", pattern)) {
+            Ok(re) => { if !re.is_match(value) { return Err(format!("Не відповідає шаблону {}", pattern)); } }
+            Err(_) => return Err(format!("Невірний regex у конфігу: {}", pattern)),
+        }
+    }
+    Ok(())
+}
+
+// Початкові значення полів: default, або перший варіант для списків
+pub fn build_form(params: Vec<CommandParam>, targets: &[Target]) -> Vec<FormField> {
+    params.into_iter().map(|param| {
+        let value = match (&param.default, &param.kind) {
+            (Some(d), _) => d.clone(),
+            (None, ParamKind::Choice) => param.choices.first().cloned().unwrap_or_default(),
+            (None, ParamKind::Target) => targets.first().map(|t| t.name.clone()).unwrap_or_default(),
+            (None, _) => String::new(),
+        };
+        FormField { param, value, error: None }
+    }).collect()
+}
+
+// Для списків Left/Right перебирають варіанти
+pub fn cycle_choice(field: &mut FormField, targets: &[Target], forward: bool) {
+    let options: Vec<String> = match field.param.kind {
+        ParamKind::Choice => field.param.choices.clone(),
+        ParamKind::Target => targets.iter().map(|t| t.name.clone()).collect(),
+        _ => return,
+    };
+    if options.is_empty() { return; }
+    let pos = options.iter().position(|o| *o == field.value);
+    let next = match (pos, forward) {
+        (Some(i), true) => (i + 1) % options.len(),
+        (Some(i), false) => (i + options.len() - 1) % options.len(),
+        (None, _) => 0,
+    };
+    field.value = options[next].clone();
+    field.error = None;
+}
+
+// Значення форми -> словник для expand_template
+pub fn form_values(fields: &[FormField], targets: &[Target]) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for field in fields {
+        let value = field.value.trim().to_string();
+        if field.param.kind == ParamKind::Target {
+            if let Some(target) = targets.iter().find(|t| t.name == value) {
+                values.extend(target_values(&field.param.name, target));
+                continue;
+            }
+        }
+        values.insert(field.param.name.clone(), value);
+    }
+    values
+}
+
+const TASK_ID_LEN: usize = 6;
+
+// "Backup DB ^a1b2c3" -> ("Backup DB", "a1b2c3"); без ID — ("Backup DB", "")
+pub fn split_task_id(text: &str) -> (&str, &str) {
+    let text = text.trim_end();
+    let (title, id) = match text.rsplit_once('^') { Some(parts) => parts, None => return (text, "") };
+    let valid = id.len() == TASK_ID_LEN && id.chars().all(|c| c.is_ascii_digit() || c.is_ascii_lowercase()) && (title.is_empty() || title.ends_with(' '));
+    if valid { (title.trim_end(), id) } else { (text, "") }
+}
+
+fn random_task_id() -> String {
+    format!("{:06x}", RandomState::new().build_hasher().finish() & 0xff_ffff)
+}
+
+// ID, якого ще немає серед `taken`
+fn unique_task_id(taken: &HashSet<String>) -> String {
+    let mut id = random_task_id();
+    while taken.contains(&id) { id = random_task_id(); }
+    id
+}
+
+fn task_ids(lines: &[String]) -> HashSet<String> {
+    lines.iter().filter(|l| is_task_line(l)).map(|l| split_task_id(l).1.to_string()).collect()
+}
+
+// ID для нового завдання, що не збігається з жодним у todo.txt
+pub fn new_task_id(lines: &[String]) -> String {
+    unique_task_id(&task_ids(lines))
+}
+
+fn is_task_line(line: &str) -> bool {
+    line.trim().find("- [").is_some_and(|start| line.trim()[start..].contains(']'))
+}
+
+// Дописує " ^id" рядкам завдань без ID і повторам (скопійований рядок). None — усе вже гаразд
+pub fn ensure_task_ids(lines: &[String]) -> Option<Vec<String>> {
+    let mut taken = task_ids(lines);
+    let mut seen = HashSet::new();
+    let mut changed = false;
+    let new_lines = lines.iter().map(|line| {
+        if !is_task_line(line) { return line.clone(); }
+        let (text, id) = split_task_id(line);
+        if !id.is_empty() && seen.insert(id.to_string()) { return line.clone(); }
+        let id = unique_task_id(&taken);
+        taken.insert(id.clone());
+        seen.insert(id.clone());
+        changed = true;
+        format!("{} ^{}", text, id)
+    }).collect();
+    changed.then_some(new_lines)
+}
+
+// Рядок відповідного завдання: за ID, а якщо його ще не присвоїли — за точною назвою
+pub fn is_line_of_task(line: &str, id: &str, title: &str) -> bool {
+    if !is_task_line(line) { return false; }
+    match parse_tasks_from_text(line).first() {
+        Some(task) if !id.is_empty() => task.id == id,
+        Some(task) => task.title == title,
+        None => false,
+    }
+}
+
+// Рядок завдання і його опис (наступні непорожні рядки до іншого завдання)
+pub fn task_block(lines: &[String], id: &str, title: &str) -> Option<Range<usize>> {
+    let start = lines.iter().position(|l| is_line_of_task(l, id, title))?;
+    let len = lines[start + 1..].iter().take_while(|l| !l.trim().is_empty() && !is_task_line(l)).count();
+    Some(start..start + len + 1)
+}
+
+// Підсумок останнього запуску run:"..." — рядок "↳ ..." в описі завдання (попередній замінюється)
+pub fn attach_run_result(lines: &[String], id: &str, title: &str, result: &str) -> Option<Vec<String>> {
+    let block = task_block(lines, id, title)?;
+    let mut new_lines: Vec<String> = lines[..block.start].to_vec();
+    new_lines.extend(lines[block.clone()].iter().filter(|l| !l.trim_start().starts_with('↳')).cloned());
+    new_lines.push(format!("      ↳ {}", result.replace("- [", "-[")));
+    new_lines.extend_from_slice(&lines[block.end..]);
+    Some(new_lines)
+}
+
+// Вміст дужок рядка завдання ("2026-10-20 14:00", "09:00 mon-fri"); для виконаного — пусто
+pub fn schedule_text(line: &str) -> String {
+    let inside = line.split_once("- [").and_then(|(_, rest)| rest.split_once(']')).map(|(inside, _)| inside.trim()).unwrap_or_default();
+    if inside.eq_ignore_ascii_case("x") { String::new() } else { inside.to_string() }
+}
+
+// Done: рядки цього завдання -> [x], повторювані — на наступне спрацювання.
+// None — відкритого рядка вже немає (закрили чи видалили вручну)
+pub fn complete_task(lines: &[String], id: &str, title: &str, now: NaiveDateTime) -> Option<Vec<String>> {
+    let mut modified = false;
+    let new_lines = lines.iter().map(|line| {
+        if !is_line_of_task(line, id, title) || line.contains("[x]") || line.contains("[X]") { return line.clone(); }
+        if let Some(rescheduled) = complete_line(line, now) { modified = true; return rescheduled; }
+        let start_bracket = line.find('[').unwrap_or(0);
+        let end_bracket = line.find(']').unwrap_or(line.len());
+        if end_bracket <= start_bracket { return line.clone(); }
+        modified = true;
+        format!("{}[x]{}", &line[..start_bracket], &line[end_bracket + 1..])
+    }).collect();
+    modified.then_some(new_lines)
+}
+
+// Зворотне до complete_task для одноразових: [x] -> [ ] (час не відновлюється)
+pub fn reopen_task(lines: &[String], id: &str, title: &str) -> Option<Vec<String>> {
+    let row = lines.iter().position(|l| is_line_of_task(l, id, title) && (l.contains("[x]") || l.contains("[X]")))?;
+    let mut new_lines = lines.to_vec();
+    new_lines[row] = new_lines[row].replacen("[x]", "[ ]", 1).replacen("[X]", "[ ]", 1);
+    Some(new_lines)
+}
+
+fn is_tag(word: &str) -> bool {
+    word.strip_prefix('#').is_some_and(|tag| tag.starts_with(char::is_alphabetic) && tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_'))
+}
+
+fn priority_marker(word: &str) -> Option<u8> {
+    (!word.is_empty() && word.len() <= 3 && word.chars().all(|c| c == '!')).then_some(word.len() as u8)
+}
+
+// `run:"IP Config"` або `run:Ping` -> (текст без нього, назва команди)
+fn take_command(text: &str) -> (String, Option<String>) {
+    let Some(start) = text.find("run:").filter(|s| *s == 0 || text[..*s].ends_with(' ')) else { return (text.to_string(), None) };
+    let rest = &text[start + 4..];
+    let (name, tail) = match rest.strip_prefix('"') {
+        Some(quoted) => match quoted.split_once('"') { Some(parts) => parts, None => return (text.to_string(), None) },
+        None => rest.split_once(' ').unwrap_or((rest, "")),
+    };
+    if name.trim().is_empty() { return (text.to_string(), None); }
+    (format!("{}{}", &text[..start], tail), Some(name.trim().to_string()))
+}
+
+// Маркери з назви завдання: "!! Restart Zabbix @Zbx1 run:"Restart" #ops"
+struct TitleMarkers { title: String, priority: u8, tags: Vec<String>, target: Option<String>, command: Option<String> }
+
+fn split_markers(text: &str) -> TitleMarkers {
+    let (text, command) = take_command(text);
+    let mut markers = TitleMarkers { title: String::new(), priority: 0, tags: Vec::new(), target: None, command };
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        if let Some(level) = priority_marker(word) { markers.priority = markers.priority.max(level); }
+        else if is_tag(word) { markers.tags.push(word[1..].to_lowercase()); }
+        else if let Some(target) = word.strip_prefix('@').filter(|t| !t.is_empty()) { markers.target = Some(target.to_string()); }
+        else { words.push(word); }
+    }
+    markers.title = words.join(" ");
+    markers
+}
+
+// Назва з маркерами, як її пишуть у todo.txt (для редагування у візарді)
+pub fn title_with_markers(task: &Task) -> String {
+    let mut parts = Vec::new();
+    if task.priority > 0 { parts.push("!".repeat(task.priority as usize)); }
+    parts.push(task.title.clone());
+    parts.extend(task.target.iter().map(|t| format!("@{}", t)));
+    parts.extend(task.command.iter().map(|c| format!("run:\"{}\"", c)));
+    parts.extend(task.tags.iter().map(|t| format!("#{}", t)));
+    parts.join(" ")
+}
+
+// Фільтр панелі Schedule: "#tag" — тег, "!".."!!!" — пріоритет не нижче, решта — підрядок назви. Усі умови разом
+pub fn task_matches(task: &Task, filter: &str) -> bool {
+    filter.split_whitespace().all(|word| {
+        if let Some(level) = priority_marker(word) { return task.priority >= level; }
+        match word.strip_prefix('#') {
+            Some(tag) if !tag.is_empty() => task.tags.iter().any(|t| t.starts_with(&tag.to_lowercase())),
+            _ => task.title.to_lowercase().contains(&word.to_lowercase()),
+        }
+    })
 }
 
 // Головна функція синхронізації: Текст -> Список завдань
@@ -57,25 +466,33 @@ pub fn parse_tasks_from_text(content: &str) -> Vec<Task> {
             // Шукаємо закриваючу дужку
             if let Some(end_bracket) = rest.find(']') {
                 let content_inside = &rest[..end_bracket]; // Це "14:00", "x", " " тощо
-                let title_part = &rest[end_bracket + 1..].trim();
+                let (title_part, id) = split_task_id(rest[end_bracket + 1..].trim());
 
                 // 1. Визначаємо статус виконання
-                // Якщо всередині є 'x' або 'X' — завдання виконане
-                let completed = content_inside.to_lowercase().contains('x');
-
-                // 2. Визначаємо час
-                // Якщо це не 'x' і не пусто, пробуємо парсити як час
-                let time = if !completed && !content_inside.trim().is_empty() {
-                    content_inside.trim().to_string()
-                } else {
-                    String::new()
+                // Лише 'x' або 'X' — завдання виконане (у правилах повтору теж бувають літери)
+                let completed = content_inside.trim().eq_ignore_ascii_case("x");
+
+                // 2. Визначаємо дату, час і правило повтору ("09:00 mon-fri")
+                // Нерозпізнаний вміст лишаємо в `time` як є
+                let (date, time, recurrence) = match parse_schedule(content_inside) {
+                    _ if completed => (String::new(), String::new(), None),
+                    Some(schedule) => schedule,
+                    None => (String::new(), content_inside.trim().to_string(), None),
                 };
 
+                let TitleMarkers { title, priority, tags, target, command } = split_markers(title_part);
                 current_task = Some(Task {
-                    title: title_part.to_string(),
+                    id: id.to_string(),
+                    title,
+                    priority,
+                    tags,
+                    target,
+                    command,
                     description: String::new(), // Опис поки пустий, заповнимо далі якщо є
                     time,
                     completed,
+                    date,
+                    recurrence,
                 });
             }
         }
@@ -88,4 +505,69 @@ pub fn parse_tasks_from_text(content: &str) -> Vec<Task> {
 
     if let Some(t) = current_task { tasks.push(t); }
     tasks
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_values_are_not_expanded_again() {
+        let values: HashMap<String, String> = [("host", "{x}"), ("x", "boom"), ("INPUT", "%INPUT% {host}")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!(expand_template("ping {host} {x} {missing} & { $_ } {{x}}", &values), "ping {x} boom {missing} & { $_ } {boom}");
+        assert_eq!(expand_template("echo %INPUT%", &values), "echo %INPUT% {host}");
+    }
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn id_survives_retitling() {
+        let edited = lines(&["- [10:00 daily] Nightly backup !! #ops ^a1b2c3", "  check logs"]);
+        assert_eq!(ensure_task_ids(&edited), None);
+        let task = &parse_tasks_from_text(&edited.join("\n"))[0];
+        assert_eq!((task.id.as_str(), task.title.as_str()), ("a1b2c3", "Nightly backup"));
+        // Завдання, збережене ще зі старою назвою, знаходиться за ID
+        assert!(is_line_of_task(&edited[0], "a1b2c3", "Backup DB"));
+        assert_eq!(task_block(&edited, "a1b2c3", "Backup DB"), Some(0..2));
+    }
+
+    #[test]
+    fn copied_lines_get_unique_ids() {
+        let text = lines(&["- [09:00] Backup ^a1b2c3", "- [09:00] Backup ^a1b2c3", "- [10:00] Other ^a1b2c3", "- [11:00] Report ^d4e5f6"]);
+        let fixed = ensure_task_ids(&text).unwrap();
+        assert_eq!(fixed[0], text[0]);
+        assert_eq!(fixed[3], text[3]);
+        let ids: Vec<&str> = fixed.iter().map(|l| split_task_id(l).1).collect();
+        assert!(ids.iter().all(|id| id.len() == TASK_ID_LEN));
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+        assert_eq!(ensure_task_ids(&fixed), None);
+    }
+
+    #[test]
+    fn title_match_is_exact() {
+        let text = lines(&["- [08:00] Backup DB2", "- [09:00] Backup"]);
+        assert!(!is_line_of_task(&text[0], "", "Backup"));
+        assert!(is_line_of_task(&text[1], "", "Backup"));
+        assert_eq!(task_block(&text, "", "Backup"), Some(1..2));
+        assert!(!is_line_of_task("- [08:00] Backup DB2 ^bbbbbb", "aaaaaa", "Backup"));
+    }
+
+    #[test]
+    fn lines_without_id_get_one() {
+        let text = lines(&["# Notes", "- [09:00] Backup", "  опис ^не id", "- [ ] Call Bob ^abc", "- [x] Done ^a1b2c3"]);
+        let fixed = ensure_task_ids(&text).unwrap();
+        assert_eq!(fixed[0], text[0]);
+        assert_eq!(fixed[2], text[2]);
+        assert_eq!(fixed[4], text[4]);
+        for (i, title) in [(1, "- [09:00] Backup"), (3, "- [ ] Call Bob ^abc")] {
+            let (rest, id) = split_task_id(&fixed[i]);
+            assert_eq!(rest, title);
+            assert_eq!(id.len(), TASK_ID_LEN);
+        }
+        let tasks = parse_tasks_from_text(&fixed.join("\n"));
+        assert!(tasks.iter().all(|t| !t.id.is_empty()));
+        assert_eq!(tasks[1].title, "Call Bob ^abc");
+    }
+}